@@ -1,4 +1,36 @@
+use std::path::Path;
+
+// Not everything in the merge module is needed here
+#[allow(dead_code)]
+#[path = "src/catalog_merge.rs"]
+mod catalog_merge;
+
+/// Embed `packages/` merged into one catalog if the catalog is split, or
+/// `packages.toml` as-is otherwise.
+fn embed_catalog() {
+    println!("cargo:rerun-if-changed=packages");
+    println!("cargo:rerun-if-changed=packages.toml");
+    println!("cargo:rerun-if-changed=src/catalog_merge.rs");
+
+    let dir = Path::new("packages");
+    let raw = if catalog_merge::is_catalog_dir(dir) {
+        match catalog_merge::merge_dir(dir) {
+            Ok(merged) => merged.text,
+            Err(e) => panic!("failed to merge the package catalog: {e}"),
+        }
+    } else {
+        std::fs::read_to_string("packages.toml").expect("packages.toml should be readable")
+    };
+
+    let out = Path::new(&std::env::var("OUT_DIR").unwrap()).join("packages.toml");
+    std::fs::write(out, raw).unwrap();
+}
+
 fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=assets/icon.ico");
+    embed_catalog();
+
     if std::env::var("CARGO_CFG_WINDOWS").is_ok() {
         let mut res = winres::WindowsResource::new();
         res.set_icon("assets/icon.ico");
@@ -0,0 +1,848 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+use serde::Deserialize;
+use serde_json::Value as Json;
+use toml::Spanned;
+
+#[allow(dead_code)]
+#[path = "../catalog_merge.rs"]
+mod catalog_merge;
+#[allow(dead_code)]
+#[path = "../catalog_types.rs"]
+mod catalog_types;
+#[allow(dead_code)]
+#[path = "../profile.rs"]
+mod profile;
+
+use catalog_types::{
+    Action, Category, Detect, Download, EnvScope, FileType, Installer, Package, PostInstall, RunAs,
+    Step,
+};
+use profile::Profile;
+
+#[derive(Debug, Deserialize)]
+struct CatalogFile {
+    schema_version: Option<Spanned<String>>,
+    min_app_version: Option<Spanned<String>>,
+    #[serde(default)]
+    categories: Vec<Spanned<Category>>,
+    #[serde(default)]
+    profiles: Vec<Spanned<Profile>>,
+    /// Read into `Package` one at a time, so a package that doesn't fit is
+    /// reported on its own line without hiding problems in the rest.
+    packages: Vec<Spanned<toml::Value>>,
+}
+
+/// A package that deserialized, with the table it was read from.
+struct Entry<'a> {
+    line: usize,
+    pkg: Package,
+    table: &'a toml::Value,
+}
+
+/// The `type` key of a table such as `installer`, or "" without one.
+fn type_name(table: Option<&toml::Value>) -> &str {
+    table
+        .and_then(|t| t.get("type"))
+        .and_then(toml::Value::as_str)
+        .unwrap_or_default()
+}
+
+/// Names of the fields that are set but blank.
+fn blank_fields<'a>(fields: &[(&'a str, Option<&String>)]) -> Vec<&'a str> {
+    fields
+        .iter()
+        .filter(|(_, value)| value.is_some_and(|v| v.trim().is_empty()))
+        .map(|(field, _)| *field)
+        .collect()
+}
+
+/// Problems with an installer that its type doesn't rule out: blank
+/// fields, malformed IDs and downloads that can't work.
+fn installer_problems(installer: &Installer, kind: &str) -> Vec<String> {
+    let fields = match installer {
+        Installer::Winget { id } | Installer::Msstore { id } => vec![("id", Some(id))],
+        Installer::Scoop { name, bucket } => {
+            vec![("name", Some(name)), ("bucket", bucket.as_ref())]
+        }
+        Installer::Choco { name }
+        | Installer::Cargo { name }
+        | Installer::Npm { name }
+        | Installer::Pipx { name }
+        | Installer::PowershellModule { name } => vec![("name", Some(name))],
+        Installer::Command { command } => vec![("command", Some(command))],
+        Installer::Download(download) => vec![
+            ("url", Some(&download.url)),
+            ("sha256", Some(&download.sha256)),
+            ("run", download.run.as_ref()),
+            ("extract_to", download.extract_to.as_ref()),
+        ],
+        Installer::Unsupported => return vec![format!("unknown installer type '{kind}'")],
+    };
+
+    let mut problems: Vec<String> = blank_fields(&fields)
+        .into_iter()
+        .map(|field| format!("installer '{field}' is empty"))
+        .collect();
+    match installer {
+        Installer::Winget { id } | Installer::Msstore { id } if !is_valid_winget_id(id) => {
+            problems.push(format!("malformed {kind} id '{id}'"));
+        }
+        Installer::Download(download) => problems.extend(download_problems(download)),
+        _ => {}
+    }
+    problems
+}
+
+fn download_problems(download: &Download) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !download.url.starts_with("https://") {
+        problems.push(format!("download url '{}' is not https", download.url));
+    }
+    let sha256 = &download.sha256;
+    if !(sha256.len() == 64 && sha256.bytes().all(|b| b.is_ascii_hexdigit())) {
+        problems.push("sha256 must be 64 hex characters".into());
+    }
+    problems.extend(download.file_name_problem());
+
+    let zip_only = download.run.is_some() || download.extract_to.is_some();
+    match download.file_type() {
+        Some(FileType::Exe | FileType::Msi) if zip_only => {
+            problems.push("run and extract_to only apply to zip downloads".into());
+        }
+        Some(FileType::Zip) if !zip_only => {
+            problems.push("zip download needs run or extract_to".into());
+        }
+        None => problems.push("can't tell the file type from the url; set file_type".into()),
+        _ => {}
+    }
+    problems
+}
+
+/// Problems with a detect rule that its type doesn't rule out: blank
+/// fields, a bad regex or registry root.
+fn detect_problems(detect: &Detect, kind: &str) -> Vec<String> {
+    let fields = match detect {
+        Detect::File { path } | Detect::Dir { path } => vec![("path", Some(path))],
+        Detect::Command {
+            command,
+            version_regex,
+            ..
+        } => vec![
+            ("command", Some(command)),
+            ("version_regex", version_regex.as_ref()),
+        ],
+        Detect::Registry { key, value } => vec![("key", Some(key)), ("value", value.as_ref())],
+        Detect::Unsupported => return vec![format!("unknown detect type '{kind}'")],
+    };
+
+    let mut problems: Vec<String> = blank_fields(&fields)
+        .into_iter()
+        .map(|field| format!("detect '{field}' is empty"))
+        .collect();
+    match detect {
+        Detect::Command {
+            version_regex: Some(pattern),
+            ..
+        } => {
+            if let Err(e) = regex::Regex::new(pattern) {
+                // The last line of a regex error is the message itself
+                let message = e.to_string();
+                let message = message.lines().last().unwrap_or_default().trim();
+                problems.push(format!("invalid version_regex: {message}"));
+            }
+        }
+        Detect::Registry { key, .. } => {
+            let root = key
+                .split('\\')
+                .next()
+                .unwrap_or_default()
+                .to_ascii_uppercase();
+            let roots = [
+                "HKLM",
+                "HKCU",
+                "HKCR",
+                "HKU",
+                "HKCC",
+                "HKEY_LOCAL_MACHINE",
+                "HKEY_CURRENT_USER",
+                "HKEY_CLASSES_ROOT",
+                "HKEY_USERS",
+                "HKEY_CURRENT_CONFIG",
+            ];
+            if !roots.contains(&root.as_str()) {
+                problems.push(format!(
+                    "registry key '{key}' has no valid root (e.g. HKLM)"
+                ));
+            }
+        }
+        _ => {}
+    }
+    problems
+}
+
+/// Problems with a post-install step that its type doesn't rule out: blank
+/// fields and settings that can't work together.
+fn step_problems(step: &Step, kind: &str) -> Vec<String> {
+    let fields = match step.action {
+        Action::Cmd { ref command } => vec![("command", Some(command))],
+        Action::Powershell { ref script } => vec![("script", Some(script))],
+        // An empty value is how set-env clears a variable
+        Action::SetEnv { ref name, .. } => vec![("name", Some(name))],
+        Action::CopyFile { ref from, ref to } => vec![("from", Some(from)), ("to", Some(to))],
+        Action::Unsupported => return vec![format!("unknown step type '{kind}'")],
+    };
+
+    let mut problems: Vec<String> = blank_fields(&fields)
+        .into_iter()
+        .map(|field| format!("step '{field}' is empty"))
+        .collect();
+    if step.label.as_ref().is_some_and(|l| l.trim().is_empty()) {
+        problems.push("step label is empty".into());
+    }
+    if step.run_as == RunAs::User {
+        match step.action {
+            Action::SetEnv {
+                scope: EnvScope::Machine,
+                ..
+            } => problems.push("machine-wide variables need run_as = \"elevated\"".into()),
+            Action::CopyFile { .. } => problems.push("copy-file steps always run elevated".into()),
+            _ => {}
+        }
+    }
+    if step.timeout == Some(0) {
+        problems.push("timeout must be at least 1 second".into());
+    }
+    problems
+}
+
+/// A single problem found in the catalog, tied to a line in the source file.
+struct Problem {
+    line: usize,
+    id: String,
+    message: String,
+}
+
+/// 1-based line number of a byte offset in `raw`.
+fn line_of(raw: &str, offset: usize) -> usize {
+    raw[..offset.min(raw.len())].matches('\n').count() + 1
+}
+
+/// True for `Publisher.Package[.More]` identifiers or Microsoft Store product IDs.
+fn is_valid_winget_id(id: &str) -> bool {
+    let is_store_id = id.len() == 12
+        && id.starts_with(['9', 'X'])
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit());
+    if is_store_id {
+        return true;
+    }
+
+    let segments: Vec<&str> = id.split('.').collect();
+    segments.len() >= 2
+        && segments.iter().all(|s| {
+            !s.is_empty()
+                && s.bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'+'))
+        })
+}
+
+/// True for BCP 47 style tags such as `en-US` or `de`.
+fn is_valid_locale(locale: &str) -> bool {
+    let mut parts = locale.split('-');
+    let language = parts.next().unwrap_or_default();
+    (2..=3).contains(&language.len())
+        && language.bytes().all(|b| b.is_ascii_lowercase())
+        && parts.all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_alphanumeric()))
+}
+
+/// Keys in `value` that `schema` doesn't declare, as dotted paths. Serde
+/// skips them silently, so a misspelt or misplaced field would otherwise
+/// go unnoticed.
+fn unknown_keys(root: &Json, schema: &Json, value: &toml::Value, path: &str) -> Vec<String> {
+    match value {
+        toml::Value::Table(table) => {
+            let mut properties = serde_json::Map::new();
+            // A `type` this version doesn't know is reported on its own
+            if !declared_properties(root, schema, table, &mut properties) {
+                return Vec::new();
+            }
+            table
+                .iter()
+                .flat_map(|(key, child)| {
+                    let path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    match properties.get(key) {
+                        Some(child_schema) => unknown_keys(root, child_schema, child, &path),
+                        None => vec![path],
+                    }
+                })
+                .collect()
+        }
+        toml::Value::Array(items) => match items_schema(root, schema) {
+            Some(item_schema) => items
+                .iter()
+                .enumerate()
+                .flat_map(|(n, item)| {
+                    unknown_keys(root, item_schema, item, &format!("{path}[{n}]"))
+                })
+                .collect(),
+            None => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// The definition a `$ref` points to, if `schema` has one.
+fn definition<'a>(root: &'a Json, schema: &Json) -> Option<&'a Json> {
+    let name = schema.get("$ref")?.as_str()?.rsplit('/').next()?;
+    root["$defs"].get(name)
+}
+
+/// Add the properties `schema` declares for `table` to `found`. Of a tagged
+/// enum's variants only the one named by the table's `type` counts; false
+/// if there is none.
+fn declared_properties(
+    root: &Json,
+    schema: &Json,
+    table: &toml::Table,
+    found: &mut serde_json::Map<String, Json>,
+) -> bool {
+    if let Some(properties) = schema.get("properties").and_then(Json::as_object) {
+        found.extend(properties.clone());
+    }
+    let mut known = true;
+    let parts = schema.get("allOf").and_then(Json::as_array).into_iter();
+    for part in definition(root, schema).into_iter().chain(parts.flatten()) {
+        known &= declared_properties(root, part, table, found);
+    }
+    for key in ["anyOf", "oneOf"] {
+        let Some(variants) = schema.get(key).and_then(Json::as_array) else {
+            continue;
+        };
+        let fitting: Vec<&Json> = variants.iter().filter(|v| fits(v, table)).collect();
+        known &= !fitting.is_empty();
+        for variant in fitting {
+            known &= declared_properties(root, variant, table, found);
+        }
+    }
+    known
+}
+
+/// Whether a variant schema can describe `table`: it allows objects, and
+/// its `type` constant, if it has one, is the table's.
+fn fits(variant: &Json, table: &toml::Table) -> bool {
+    let is_object = match variant.get("type") {
+        None => true,
+        Some(Json::Array(types)) => types.iter().any(|t| t == "object"),
+        Some(kind) => kind == "object",
+    };
+    let tag = table.get("type").and_then(toml::Value::as_str);
+    is_object
+        && variant
+            .pointer("/properties/type/const")
+            .is_none_or(|name| name.as_str() == tag)
+}
+
+/// Schema of the items of the arrays `schema` accepts.
+fn items_schema<'a>(root: &'a Json, schema: &'a Json) -> Option<&'a Json> {
+    if let Some(items) = schema.get("items") {
+        return Some(items);
+    }
+    let variants = ["allOf", "anyOf", "oneOf"]
+        .iter()
+        .filter_map(|key| schema.get(key)?.as_array())
+        .flatten();
+    definition(root, schema)
+        .into_iter()
+        .chain(variants)
+        .find_map(|variant| items_schema(root, variant))
+}
+
+fn lint(raw: &str, file: &CatalogFile) -> Vec<Problem> {
+    let mut problems = lint_versions(raw, file);
+    problems.extend(lint_categories(raw, file));
+    problems.extend(lint_profiles(raw, file));
+
+    let mut entries = Vec::new();
+    for (n, spanned) in file.packages.iter().enumerate() {
+        let table = spanned.get_ref();
+        let line = line_of(raw, spanned.span().start);
+        match Package::deserialize(table.clone()) {
+            Ok(pkg) => entries.push(Entry { line, pkg, table }),
+            Err(e) => problems.push(Problem {
+                line,
+                id: match table.get("id").and_then(toml::Value::as_str) {
+                    Some(id) => id.to_string(),
+                    None => format!("#{}", n + 1),
+                },
+                message: e.message().to_string(),
+            }),
+        }
+    }
+
+    let schema = schemars::schema_for!(Package);
+    let schema = schema.as_value();
+    let mut first_seen: HashMap<&str, usize> = HashMap::new();
+    let known_categories: HashSet<&str> = file
+        .categories
+        .iter()
+        .map(|c| c.get_ref().slug.as_str())
+        .collect();
+    let known_profiles: HashSet<&str> = file
+        .profiles
+        .iter()
+        .map(|p| p.get_ref().slug.as_str())
+        .collect();
+
+    for &Entry {
+        line,
+        ref pkg,
+        table,
+    } in &entries
+    {
+        let mut report = |message: String| {
+            problems.push(Problem {
+                line,
+                id: pkg.id.clone(),
+                message,
+            });
+        };
+
+        if let Some(first) = first_seen.get(pkg.id.as_str()) {
+            report(format!("duplicate id (first defined on line {first})"));
+        } else {
+            first_seen.insert(&pkg.id, line);
+        }
+
+        for key in unknown_keys(schema, schema, table, "") {
+            report(format!("unknown key '{key}'"));
+        }
+
+        if !known_categories.contains(pkg.category.as_str()) {
+            report(format!("unknown category '{}'", pkg.category));
+        }
+
+        for profile in &pkg.profiles {
+            if !known_profiles.contains(profile.as_str()) {
+                report(format!("unknown profile '{profile}'"));
+            }
+        }
+
+        let methods = [
+            pkg.winget_id.is_some(),
+            pkg.install_command.is_some(),
+            pkg.installer.is_some(),
+        ];
+        match methods.iter().filter(|&&set| set).count() {
+            0 => report("none of winget_id, install_command, or installer is set".into()),
+            1 => {}
+            _ => report("set only one of winget_id, install_command, and installer".into()),
+        }
+
+        let installer_kind = type_name(table.get("installer"));
+        if let Some(ref installer) = pkg.installer {
+            for problem in installer_problems(installer, installer_kind) {
+                report(problem);
+            }
+        }
+        let is_winget =
+            pkg.winget_id.is_some() || matches!(pkg.installer, Some(Installer::Winget { .. }));
+        let uses_winget = is_winget || matches!(pkg.installer, Some(Installer::Msstore { .. }));
+
+        if let Some(ref winget_id) = pkg.winget_id
+            && !is_valid_winget_id(winget_id)
+        {
+            report(format!("malformed winget_id '{winget_id}'"));
+        }
+
+        let mut seen_tags = HashSet::new();
+        for tag in &pkg.tags {
+            let is_kebab = !tag.is_empty()
+                && tag
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-');
+            if !is_kebab {
+                report(format!("tag '{tag}' is not lowercase kebab-case"));
+            }
+            if !seen_tags.insert(tag.as_str()) {
+                report(format!("duplicate tag '{tag}'"));
+            }
+        }
+
+        for (n, arch) in pkg.arch.iter().enumerate() {
+            if pkg.arch[..n].contains(arch) {
+                report(format!("duplicate arch '{arch}'"));
+            }
+        }
+
+        if let (Some(min), Some(max)) = (pkg.min_os_build, pkg.max_os_build)
+            && min > max
+        {
+            report(format!("min_os_build {min} is above max_os_build {max}"));
+        }
+        for build in [pkg.min_os_build, pkg.max_os_build].into_iter().flatten() {
+            if build < 10240 {
+                report(format!("OS build {build} predates Windows 10 (10240)"));
+            }
+        }
+
+        if pkg.version.is_some() && !is_winget {
+            report("version pins only apply to winget packages".into());
+        }
+
+        let winget = &pkg.winget;
+        if winget.summary().is_some() && !uses_winget {
+            report("winget options set on a package not installed by winget".into());
+        }
+        if winget.override_args.is_some() && winget.custom.is_some() {
+            report("winget override replaces all switches; custom has no effect".into());
+        }
+        if let Some(ref locale) = winget.locale
+            && !is_valid_locale(locale)
+        {
+            report(format!("malformed winget locale '{locale}'"));
+        }
+        for arg in &winget.extra_args {
+            if matches!(arg.as_str(), "--silent" | "--interactive" | "-h" | "-i") {
+                report(format!(
+                    "extra_args '{arg}' conflicts with the install mode setting"
+                ));
+            }
+        }
+
+        let command = match pkg.installer {
+            Some(Installer::Command { ref command }) => Some(command.as_str()),
+            _ => pkg.install_command.as_deref(),
+        };
+        let is_browser_download = command.is_some_and(|c| c.starts_with("start http"));
+        if is_browser_download && pkg.post_install.is_some() {
+            report("post_install has no effect on a browser download".into());
+        }
+
+        if let Some(ref detect) = pkg.detect {
+            // Every backend but commands and downloads lists its own packages
+            let listed_by = match pkg.installer {
+                Some(
+                    Installer::Command { .. } | Installer::Download(_) | Installer::Unsupported,
+                ) => None,
+                Some(_) => Some(installer_kind),
+                None if pkg.winget_id.is_some() => Some("winget"),
+                None => None,
+            };
+            if let Some(kind) = listed_by {
+                report(format!(
+                    "detect has no effect: {kind} packages are detected by their installer"
+                ));
+            }
+            for problem in detect_problems(detect, type_name(table.get("detect"))) {
+                report(problem);
+            }
+        }
+
+        if pkg
+            .uninstall_command
+            .as_ref()
+            .is_some_and(|c| c.trim().is_empty())
+        {
+            report("uninstall_command is empty".into());
+        }
+
+        let metadata = [
+            ("homepage", &pkg.homepage),
+            ("license", &pkg.license),
+            ("publisher", &pkg.publisher),
+            ("icon", &pkg.icon),
+            ("notes", &pkg.notes),
+        ];
+        for (field, value) in metadata {
+            if value.as_ref().is_some_and(|v| v.trim().is_empty()) {
+                report(format!("{field} is empty"));
+            }
+        }
+        if let Some(ref homepage) = pkg.homepage
+            && !homepage.starts_with("https://")
+            && !homepage.starts_with("http://")
+        {
+            report(format!("homepage '{homepage}' is not an http(s) URL"));
+        }
+        if let Some(ref icon) = pkg.icon
+            && lucide_icons::Icon::try_from(icon.as_str()).is_err()
+        {
+            report(format!("unknown Lucide icon '{icon}'"));
+        }
+
+        match pkg.post_install {
+            Some(PostInstall::Command(ref command)) if command.trim().is_empty() => {
+                report("post_install is empty".into());
+            }
+            Some(PostInstall::Steps(ref steps)) if steps.is_empty() => {
+                report("post_install has no steps".into());
+            }
+            Some(PostInstall::Steps(ref steps)) => {
+                for (n, step) in steps.iter().enumerate() {
+                    let kind = type_name(table.get("post_install").and_then(|p| p.get(n)));
+                    for problem in step_problems(step, kind) {
+                        report(format!("post_install step {}: {problem}", n + 1));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    problems.extend(lint_dependencies(file, &entries));
+    problems
+}
+
+/// Check the top-level `schema_version` (`major.minor`) and `min_app_version`.
+fn lint_versions(raw: &str, file: &CatalogFile) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let is_numeric = |v: &str, parts: std::ops::RangeInclusive<usize>| {
+        let segments: Vec<&str> = v.split('.').collect();
+        parts.contains(&segments.len())
+            && segments
+                .iter()
+                .all(|s| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()))
+    };
+
+    match file.schema_version {
+        None => problems.push(Problem {
+            line: 1,
+            id: "schema_version".into(),
+            message: "catalog has no schema_version".into(),
+        }),
+        Some(ref version) if !is_numeric(version.get_ref(), 2..=2) => problems.push(Problem {
+            line: line_of(raw, version.span().start),
+            id: "schema_version".into(),
+            message: format!("'{}' is not a major.minor version", version.get_ref()),
+        }),
+        Some(_) => {}
+    }
+
+    if let Some(ref version) = file.min_app_version
+        && !is_numeric(version.get_ref(), 1..=3)
+    {
+        problems.push(Problem {
+            line: line_of(raw, version.span().start),
+            id: "min_app_version".into(),
+            message: format!("'{}' is not a version number", version.get_ref()),
+        });
+    }
+
+    problems
+}
+
+/// Check `[[categories]]` entries: duplicate slugs and unknown Lucide icon names.
+fn lint_categories(raw: &str, file: &CatalogFile) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut first_seen: HashMap<&str, usize> = HashMap::new();
+
+    for spanned in &file.categories {
+        let cat = spanned.get_ref();
+        let line = line_of(raw, spanned.span().start);
+
+        if let Some(first) = first_seen.get(cat.slug.as_str()) {
+            problems.push(Problem {
+                line,
+                id: cat.slug.clone(),
+                message: format!("duplicate category (first defined on line {first})"),
+            });
+        } else {
+            first_seen.insert(&cat.slug, line);
+        }
+
+        if let Some(ref icon) = cat.icon
+            && lucide_icons::Icon::try_from(icon.as_str()).is_err()
+        {
+            problems.push(Problem {
+                line,
+                id: cat.slug.clone(),
+                message: format!("unknown Lucide icon '{icon}'"),
+            });
+        }
+    }
+
+    problems
+}
+
+/// Check `[[profiles]]` entries: duplicates, icons, and `extends` chains.
+fn lint_profiles(raw: &str, file: &CatalogFile) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut first_seen: HashMap<&str, usize> = HashMap::new();
+    let extends: HashMap<&str, Option<&str>> = file
+        .profiles
+        .iter()
+        .map(|p| (p.get_ref().slug.as_str(), p.get_ref().extends.as_deref()))
+        .collect();
+
+    if file.profiles.is_empty() {
+        problems.push(Problem {
+            line: 1,
+            id: "profiles".into(),
+            message: "catalog declares no profiles".into(),
+        });
+    }
+
+    for spanned in &file.profiles {
+        let profile = spanned.get_ref();
+        let line = line_of(raw, spanned.span().start);
+        let mut report = |message: String| {
+            problems.push(Problem {
+                line,
+                id: profile.slug.clone(),
+                message,
+            });
+        };
+
+        if let Some(first) = first_seen.get(profile.slug.as_str()) {
+            report(format!("duplicate profile (first defined on line {first})"));
+        } else {
+            first_seen.insert(&profile.slug, line);
+        }
+
+        if lucide_icons::Icon::try_from(profile.icon.as_str()).is_err() {
+            report(format!("unknown Lucide icon '{}'", profile.icon));
+        }
+
+        // Follow the `extends` chain until it ends, breaks, or loops back.
+        let mut chain = vec![profile.slug.as_str()];
+        let mut parent = profile.extends.as_deref();
+        while let Some(slug) = parent {
+            if chain.contains(&slug) {
+                report(format!("profile cycle: {} -> {slug}", chain.join(" -> ")));
+                break;
+            }
+            let Some(next) = extends.get(slug) else {
+                report(format!("extends unknown profile '{slug}'"));
+                break;
+            };
+            chain.push(slug);
+            parent = *next;
+        }
+    }
+
+    problems
+}
+
+/// Check `depends_on` references: unknown ids, self-dependencies and cycles.
+fn lint_dependencies(file: &CatalogFile, entries: &[Entry]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    // Packages that didn't deserialize still count as known
+    let known: HashSet<&str> = file
+        .packages
+        .iter()
+        .filter_map(|p| p.get_ref().get("id")?.as_str())
+        .collect();
+    let deps: HashMap<&str, &[String]> = entries
+        .iter()
+        .map(|e| (e.pkg.id.as_str(), e.pkg.depends_on.as_slice()))
+        .collect();
+
+    for &Entry { line, ref pkg, .. } in entries {
+        for dep in &pkg.depends_on {
+            let message = if *dep == pkg.id {
+                "depends on itself".to_string()
+            } else if !known.contains(dep.as_str()) {
+                format!("depends on unknown package '{dep}'")
+            } else {
+                continue;
+            };
+            problems.push(Problem {
+                line,
+                id: pkg.id.clone(),
+                message,
+            });
+        }
+
+        // Walk the dependency graph from this package; reaching it again is a cycle.
+        let mut stack: Vec<&str> = pkg.depends_on.iter().map(String::as_str).collect();
+        let mut seen: HashSet<&str> = HashSet::new();
+        while let Some(id) = stack.pop() {
+            if id == pkg.id && !pkg.depends_on.contains(&pkg.id) {
+                problems.push(Problem {
+                    line,
+                    id: pkg.id.clone(),
+                    message: "is part of a dependency cycle".into(),
+                });
+                break;
+            }
+            if seen.insert(id) {
+                stack.extend(
+                    deps.get(id)
+                        .into_iter()
+                        .flat_map(|d| d.iter().map(String::as_str)),
+                );
+            }
+        }
+    }
+
+    problems
+}
+
+fn main() -> ExitCode {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "packages.toml".into());
+
+    // A split catalog is checked as the merged file the app would load,
+    // with problems reported against the package files
+    let merged = if catalog_merge::is_catalog_dir(Path::new(&path)) {
+        match catalog_merge::merge_dir(Path::new(&path)) {
+            Ok(merged) => Some(merged),
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        None
+    };
+    let location = |line: usize| match merged {
+        Some(ref merged) => {
+            let (file, line) = merged.locate(line);
+            format!("{}:{line}", file.display())
+        }
+        None => format!("{path}:{line}"),
+    };
+
+    let raw = match merged {
+        Some(ref merged) => merged.text.clone(),
+        None => match fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(e) => {
+                eprintln!("error: failed to read {path}: {e}");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    let file: CatalogFile = match toml::from_str(&raw) {
+        Ok(file) => file,
+        Err(e) => {
+            let line = e.span().map(|s| line_of(&raw, s.start)).unwrap_or(0);
+            eprintln!("{}: {}", location(line), e.message());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let problems = lint(&raw, &file);
+    for p in &problems {
+        eprintln!("{}: [{}] {}", location(p.line), p.id, p.message);
+    }
+
+    if problems.is_empty() {
+        println!("{path}: {} packages OK", file.packages.len());
+        ExitCode::SUCCESS
+    } else {
+        eprintln!("{path}: {} problem(s) found", problems.len());
+        ExitCode::FAILURE
+    }
+}
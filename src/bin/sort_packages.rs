@@ -1,131 +1,182 @@
+//! Sort packages.toml: packages are grouped by category, in the declared
+//! category order, and listed alphabetically by name within each category,
+//! ignoring case and accents. Each category starts with a banner comment.
+//!
+//! Usage: `sort_packages [--check] [packages.toml]`
+//!
+//! The file is edited in place, so comments and field order are kept;
+//! comments above a `[[packages]]` header move with that package. With
+//! `--check`, nothing is written and the exit code says whether the file
+//! is sorted.
+
 use std::collections::HashMap;
 use std::fs;
+use std::process::ExitCode;
 
-use serde::Deserialize;
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table};
 
-#[derive(Debug, Deserialize)]
-struct CatalogFile {
-    packages: Vec<Package>,
-}
+// Shares the layout with split catalogs, so merged catalogs come out sorted
+#[allow(dead_code)]
+#[path = "../catalog_merge.rs"]
+mod catalog_merge;
+
+use catalog_merge::{BANNER_PREFIX, banner, sort_key};
 
-#[derive(Debug, Deserialize)]
-struct Package {
-    id: String,
-    name: String,
-    description: String,
-    category: String,
-    winget_id: Option<String>,
-    #[serde(default)]
-    profiles: Vec<String>,
-    post_install: Option<String>,
-    install_command: Option<String>,
+fn str_field<'a>(table: &'a Table, key: &str) -> &'a str {
+    table.get(key).and_then(Item::as_str).unwrap_or_default()
 }
 
-/// Fixed category order matching the app's display order.
-const CATEGORY_ORDER: &[&str] = &[
-    "browsers",
-    "communication",
-    "development",
-    "documents",
-    "games",
-    "microsoft-tools",
-    "multimedia",
-    "utilities",
-    "security-privacy",
-    "design",
-];
-
-fn category_display_name(slug: &str) -> &str {
-    match slug {
-        "browsers" => "Browsers",
-        "communication" => "Communication",
-        "development" => "Development",
-        "documents" => "Documents",
-        "games" => "Games",
-        "microsoft-tools" => "Microsoft Tools",
-        "multimedia" => "Multimedia",
-        "utilities" => "Utilities",
-        "security-privacy" => "Security & Privacy",
-        "design" => "Design",
-        _ => slug,
-    }
+/// The comment lines above a table header, without blank lines and banners.
+fn own_comments(table: &Table) -> String {
+    let prefix = table
+        .decor()
+        .prefix()
+        .and_then(|p| p.as_str())
+        .unwrap_or_default();
+    prefix
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty() && !line.starts_with(BANNER_PREFIX))
+        .map(|line| format!("{line}\n"))
+        .collect()
 }
 
-fn main() {
-    let path = "packages.toml";
-    let raw = fs::read_to_string(path).expect("failed to read packages.toml");
-    let file: CatalogFile = toml::from_str(&raw).expect("failed to parse packages.toml");
+/// Reorder the `[[packages]]` tables of `raw`. Returns the new text and the
+/// number of packages and categories.
+fn sort(raw: &str) -> Result<(String, usize, usize), String> {
+    let mut doc: DocumentMut = raw.parse().map_err(|e| format!("{e}"))?;
+
+    // Declared category order and display names; ties keep declaration order.
+    let mut categories: Vec<(i64, String, String)> = doc
+        .get("categories")
+        .and_then(Item::as_array_of_tables)
+        .map(|cats| {
+            cats.iter()
+                .map(|c| {
+                    let order = c.get("order").and_then(Item::as_integer).unwrap_or(0);
+                    let slug = str_field(c, "slug").to_string();
+                    let name = str_field(c, "name").to_string();
+                    (order, slug, name)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    categories.sort_by_key(|(order, ..)| *order);
+    let has_preamble = doc.contains_key("categories") || doc.contains_key("profiles");
+
+    let Some(packages) = doc
+        .get_mut("packages")
+        .and_then(Item::as_array_of_tables_mut)
+    else {
+        return Err("no [[packages]] found".into());
+    };
+    let first_position = packages.iter().filter_map(Table::position).min();
 
     // Group packages by category.
-    let mut by_category: HashMap<String, Vec<&Package>> = HashMap::new();
-    for pkg in &file.packages {
-        by_category
-            .entry(pkg.category.clone())
-            .or_default()
-            .push(pkg);
+    let mut by_category: HashMap<String, Vec<Table>> = HashMap::new();
+    for table in std::mem::take(packages) {
+        let category = str_field(&table, "category").to_string();
+        by_category.entry(category).or_default().push(table);
     }
-
-    // Sort each category alphabetically by name (case-insensitive).
-    for packages in by_category.values_mut() {
-        packages.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    let total = by_category.values().map(Vec::len).sum();
+
+    // Sort each category by name, then ID, so equal keys stay in a fixed order.
+    for tables in by_category.values_mut() {
+        tables.sort_by_cached_key(|t| {
+            let name = str_field(t, "name");
+            (
+                sort_key(name),
+                name.to_string(),
+                str_field(t, "id").to_string(),
+            )
+        });
     }
 
-    // Warn about unknown categories.
-    for cat in by_category.keys() {
-        if !CATEGORY_ORDER.contains(&cat.as_str()) {
-            eprintln!("warning: unknown category '{cat}' — appending at the end");
-        }
+    // Warn about undeclared categories.
+    let mut undeclared: Vec<String> = by_category
+        .keys()
+        .filter(|slug| !categories.iter().any(|(_, s, _)| s == *slug))
+        .cloned()
+        .collect();
+    undeclared.sort();
+    for cat in &undeclared {
+        eprintln!("warning: undeclared category '{cat}' — appending at the end");
     }
 
-    // Build ordered list of categories: known order first, then any unknown.
-    let mut ordered_categories: Vec<&str> = CATEGORY_ORDER
+    // Declared order first, then any undeclared, each with its banner title.
+    let mut ordered: Vec<(String, String)> = categories
         .iter()
-        .filter(|c| by_category.contains_key(**c))
-        .copied()
+        .filter(|(_, slug, _)| by_category.contains_key(slug))
+        .map(|(_, slug, name)| (slug.clone(), name.clone()))
         .collect();
-    for cat in by_category.keys() {
-        if !CATEGORY_ORDER.contains(&cat.as_str()) {
-            ordered_categories.push(cat.as_str());
+    ordered.extend(undeclared.into_iter().map(|slug| (slug.clone(), slug)));
+
+    let mut sorted = ArrayOfTables::new();
+    let mut position = first_position;
+    for (i, (slug, title)) in ordered.iter().enumerate() {
+        let tables = by_category.remove(slug).unwrap_or_default();
+        for (j, mut table) in tables.into_iter().enumerate() {
+            let mut prefix = String::from("\n");
+            if j == 0 {
+                if i > 0 || has_preamble {
+                    prefix.push('\n');
+                }
+                prefix.push_str(&banner(title));
+                prefix.push('\n');
+            }
+            prefix.push_str(&own_comments(&table));
+            table.decor_mut().set_prefix(prefix);
+            // Tables are written in position order, not array order
+            table.set_position(position);
+            position = position.map(|p| p + 1);
+            sorted.push(table);
         }
     }
+    *packages = sorted;
 
-    // Generate the output.
-    let mut out = String::from("# Provision — Package Catalog\n");
+    Ok((doc.to_string(), total, ordered.len()))
+}
 
-    for (i, &cat) in ordered_categories.iter().enumerate() {
-        let display = category_display_name(cat);
-        let bar = "─".repeat(57 - display.len().min(55));
+fn main() -> ExitCode {
+    let mut check = false;
+    let mut path = "packages.toml".to_string();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check = true,
+            _ => path = arg,
+        }
+    }
 
-        if i > 0 {
-            out.push('\n');
+    let raw = match fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(e) => {
+            eprintln!("error: failed to read {path}: {e}");
+            return ExitCode::FAILURE;
         }
-        out.push_str(&format!("\n# ── {display} {bar}\n"));
-
-        let packages = &by_category[cat];
-        for pkg in packages {
-            out.push_str("\n[[packages]]\n");
-            out.push_str(&format!("id = {:?}\n", pkg.id));
-            out.push_str(&format!("name = {:?}\n", pkg.name));
-            out.push_str(&format!("description = {:?}\n", pkg.description));
-            out.push_str(&format!("category = {:?}\n", pkg.category));
-            if let Some(ref winget_id) = pkg.winget_id {
-                out.push_str(&format!("winget_id = {:?}\n", winget_id));
-            }
-            out.push_str(&format!("profiles = {:?}\n", pkg.profiles));
-            if let Some(ref post_install) = pkg.post_install {
-                out.push_str(&format!("post_install = {:?}\n", post_install));
-            }
-            if let Some(ref install_command) = pkg.install_command {
-                out.push_str(&format!("install_command = {:?}\n", install_command));
-            }
+    };
+    let (out, total, categories) = match sort(&raw) {
+        Ok(sorted) => sorted,
+        Err(e) => {
+            eprintln!("error: failed to parse {path}: {e}");
+            return ExitCode::FAILURE;
         }
-    }
+    };
 
-    fs::write(path, &out).expect("failed to write packages.toml");
+    if check {
+        if out == raw {
+            println!("{path} is sorted");
+            return ExitCode::SUCCESS;
+        }
+        eprintln!("{path} is not sorted; run sort_packages");
+        return ExitCode::FAILURE;
+    }
 
-    let total: usize = by_category.values().map(|v| v.len()).sum();
-    println!(
-        "Sorted {total} packages across {} categories",
-        ordered_categories.len()
-    );
+    if out != raw
+        && let Err(e) = fs::write(&path, &out)
+    {
+        eprintln!("error: failed to write {path}: {e}");
+        return ExitCode::FAILURE;
+    }
+    println!("Sorted {total} packages across {categories} categories");
+    ExitCode::SUCCESS
 }
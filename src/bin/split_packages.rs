@@ -0,0 +1,191 @@
+//! Split packages.toml into one file per package, or merge such a directory
+//! back into packages.toml.
+//!
+//! Usage:
+//! - `split_packages [packages.toml] [packages]` writes `packages/catalog.toml`
+//!   (everything but the packages) and `packages/<category>/<id>.toml`.
+//! - `split_packages --merge [packages] [packages.toml]` writes the merged
+//!   catalog, e.g. to publish and sign it.
+//!
+//! Comments above a package move into its file. The split is checked by
+//! merging it back and comparing the result with the original.
+
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+#[allow(dead_code)]
+#[path = "../catalog_merge.rs"]
+mod catalog_merge;
+
+use catalog_merge::{BANNER_PREFIX, HEADER_FILE};
+
+const PACKAGE_HEADER: &str = "[[packages]]";
+
+fn is_comment_or_blank(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+/// The catalog with its packages sorted by ID, so catalogs that only differ
+/// in package order compare equal.
+fn normalized(raw: &str) -> Result<toml::Table, String> {
+    let mut table: toml::Table = raw.parse().map_err(|e| format!("{e}"))?;
+    if let Some(packages) = table
+        .get_mut("packages")
+        .and_then(toml::Value::as_array_mut)
+    {
+        packages.sort_by_key(|p| p.get("id").and_then(|id| id.as_str()).map(str::to_string));
+    }
+    Ok(table)
+}
+
+fn split(raw: &str, dir: &Path) -> Result<usize, String> {
+    if dir.exists() {
+        return Err(format!("{} already exists; remove it first", dir.display()));
+    }
+
+    let lines: Vec<&str> = raw.lines().collect();
+    // Each package starts at the comments above its [[packages]] header
+    let starts: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.trim_end() == PACKAGE_HEADER)
+        .map(|(i, _)| {
+            let comments = lines[..i]
+                .iter()
+                .rev()
+                .take_while(|line| is_comment_or_blank(line))
+                .count();
+            i - comments
+        })
+        .collect();
+    let Some(&first) = starts.first() else {
+        return Err("no [[packages]] found".into());
+    };
+
+    let mut files = vec![(
+        dir.join(HEADER_FILE),
+        format!("{}\n", lines[..first].join("\n").trim_end()),
+    )];
+    let ends = starts.iter().skip(1).copied().chain([lines.len()]);
+    for (start, end) in starts.iter().copied().zip(ends) {
+        let chunk = &lines[start..end];
+        let package: toml::Table = chunk
+            .join("\n")
+            .parse::<toml::Table>()
+            .map_err(|e| format!("{e}"))?
+            .get("packages")
+            .and_then(toml::Value::as_array)
+            .and_then(|p| p.first())
+            .and_then(toml::Value::as_table)
+            .cloned()
+            .unwrap_or_default();
+        let field = |key| package.get(key).and_then(toml::Value::as_str);
+        let (Some(id), Some(category)) = (field("id"), field("category")) else {
+            return Err(format!(
+                "package at line {} needs an id and a category",
+                start + 1
+            ));
+        };
+        if id.is_empty() || id.contains(['/', '\\', '.']) {
+            return Err(format!("package id '{id}' can't be used as a file name"));
+        }
+
+        let header = chunk
+            .iter()
+            .position(|line| line.trim_end() == PACKAGE_HEADER)
+            .unwrap_or_default();
+        let comments: String = chunk[..header]
+            .iter()
+            .map(|line| line.trim_end())
+            .filter(|line| !line.is_empty() && !line.starts_with(BANNER_PREFIX))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        let body = chunk[header + 1..].join("\n");
+        let path = dir.join(category).join(format!("{id}.toml"));
+        if files.iter().any(|(p, _)| *p == path) {
+            return Err(format!("duplicate package id '{id}'"));
+        }
+        files.push((path, format!("{comments}{}\n", body.trim_end())));
+    }
+
+    for (path, contents) in &files {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("{}: {e}", parent.display()))?;
+        }
+        fs::write(path, contents).map_err(|e| format!("{}: {e}", path.display()))?;
+    }
+
+    let merged = catalog_merge::merge_dir(dir)?;
+    if normalized(&merged.text)? != normalized(raw)? {
+        return Err(format!(
+            "{} doesn't merge back into the same catalog; are there tables after the packages?",
+            dir.display()
+        ));
+    }
+    Ok(files.len() - 1)
+}
+
+fn merge(dir: &Path, path: &Path) -> Result<usize, String> {
+    let merged = catalog_merge::merge_dir(dir)?;
+    let current = fs::read_to_string(path).unwrap_or_default();
+    if merged.text != current {
+        fs::write(path, &merged.text).map_err(|e| format!("{}: {e}", path.display()))?;
+    }
+    Ok(merged
+        .text
+        .lines()
+        .filter(|line| *line == PACKAGE_HEADER)
+        .count())
+}
+
+fn main() -> ExitCode {
+    let mut merging = false;
+    let mut paths = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--merge" => merging = true,
+            _ => paths.push(arg),
+        }
+    }
+    let file = "packages.toml".to_string();
+    let dir = "packages".to_string();
+
+    if merging {
+        let mut paths = paths.into_iter();
+        let dir = paths.next().unwrap_or(dir);
+        let file = paths.next().unwrap_or(file);
+        match merge(Path::new(&dir), Path::new(&file)) {
+            Ok(count) => {
+                println!("Merged {count} packages from {dir} into {file}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::FAILURE
+            }
+        }
+    } else {
+        let mut paths = paths.into_iter();
+        let file = paths.next().unwrap_or(file);
+        let dir = paths.next().unwrap_or(dir);
+        let raw = match fs::read_to_string(&file) {
+            Ok(raw) => raw,
+            Err(e) => {
+                eprintln!("error: failed to read {file}: {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+        match split(&raw, Path::new(&dir)) {
+            Ok(count) => {
+                println!("Split {count} packages from {file} into {dir}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::FAILURE
+            }
+        }
+    }
+}
@@ -0,0 +1,370 @@
+//! Check every winget and Store package in the catalog against winget
+//! itself: the ID still resolves, it hasn't changed case or publisher, and
+//! there is an installer for each architecture the package supports.
+//!
+//! Usage: `verify_packages [packages.toml] [--json report.json]`
+//!
+//! Runs `winget show` several times per package, so it takes a while. Set
+//! `WINGET` to run another program instead, e.g. a stub for testing.
+
+use std::fs;
+use std::process::{Command, ExitCode};
+
+use serde::{Deserialize, Serialize};
+use toml::Spanned;
+
+/// Architectures checked when a package doesn't declare `arch`.
+const DEFAULT_ARCHES: [&str; 2] = ["x64", "arm64"];
+
+#[derive(Debug, Deserialize)]
+struct CatalogFile {
+    packages: Vec<Spanned<Package>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Package {
+    id: String,
+    winget_id: Option<String>,
+    installer: Option<Installer>,
+    publisher: Option<String>,
+    #[serde(default)]
+    arch: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Installer {
+    #[serde(rename = "type")]
+    kind: String,
+    id: Option<String>,
+}
+
+impl Package {
+    /// The winget ID, and whether it's a Store product; `None` for packages
+    /// winget doesn't install.
+    fn winget_id(&self) -> Option<(&str, bool)> {
+        if let Some(ref id) = self.winget_id {
+            return Some((id, false));
+        }
+        let installer = self.installer.as_ref()?;
+        match installer.kind.as_str() {
+            "winget" => Some((installer.id.as_deref()?, false)),
+            "msstore" => Some((installer.id.as_deref()?, true)),
+            _ => None,
+        }
+    }
+}
+
+/// The machine-readable report written with `--json`.
+#[derive(Serialize)]
+struct Report {
+    checked: usize,
+    skipped: usize,
+    problems: usize,
+    packages: Vec<PackageReport>,
+}
+
+#[derive(Serialize)]
+struct PackageReport {
+    id: String,
+    winget_id: String,
+    line: usize,
+    /// What winget reports, if the ID resolved.
+    name: Option<String>,
+    publisher: Option<String>,
+    version: Option<String>,
+    problems: Vec<Problem>,
+}
+
+#[derive(Serialize)]
+struct Problem {
+    /// `not-found`, `id-changed`, `publisher-changed`, `missing-installer`,
+    /// or `winget-failed`.
+    kind: &'static str,
+    message: String,
+}
+
+/// The fields of `winget show` output we check.
+#[derive(Default)]
+struct Shown {
+    name: String,
+    id: String,
+    publisher: String,
+    version: String,
+}
+
+/// Run `winget show` for an exact ID, optionally for one architecture.
+/// `Ok(None)` if winget found no package (or no installer for `arch`).
+fn winget_show(
+    winget: &str,
+    id: &str,
+    store: bool,
+    arch: Option<&str>,
+) -> Result<Option<Shown>, String> {
+    let mut command = Command::new(winget);
+    command.args(["show", "--id", id, "-e"]);
+    if store {
+        command.args(["--source", "msstore"]);
+    }
+    if let Some(arch) = arch {
+        command.args(["--architecture", arch]);
+    }
+    command.arg("--accept-source-agreements");
+
+    let output = command
+        .output()
+        .map_err(|e| format!("failed to run {winget}: {e}"))?;
+    // A missing package or installer is a failed run with no "Found" line
+    let shown = parse_show(&String::from_utf8_lossy(&output.stdout));
+    Ok(shown.filter(|_| output.status.success()))
+}
+
+/// Parse the `Found <name> [<id>]` line and top-level fields of `winget
+/// show` output. Progress spinners are redrawn with `\r`, so only what's
+/// left on each line counts.
+fn parse_show(stdout: &str) -> Option<Shown> {
+    let mut shown = Shown::default();
+    let mut found = false;
+    for line in stdout.lines() {
+        let line = line.rsplit('\r').next().unwrap_or_default();
+        if let Some(rest) = line.strip_prefix("Found ")
+            && let Some((name, id)) = rest.trim_end().rsplit_once(" [")
+        {
+            shown.name = name.trim().to_string();
+            shown.id = id.trim_end_matches(']').to_string();
+            found = true;
+            continue;
+        }
+        if line.starts_with(' ') {
+            continue;
+        }
+        match line.split_once(':') {
+            Some(("Publisher", value)) => shown.publisher = value.trim().to_string(),
+            Some(("Version", value)) => shown.version = value.trim().to_string(),
+            _ => {}
+        }
+    }
+    found.then_some(shown)
+}
+
+/// Check one package. `show` looks an exact ID up like `winget_show`, so
+/// tests can stand in for winget.
+fn check(
+    show: impl Fn(&str, bool, Option<&str>) -> Result<Option<Shown>, String>,
+    pkg: &Package,
+    line: usize,
+    id: &str,
+    store: bool,
+) -> PackageReport {
+    let mut report = PackageReport {
+        id: pkg.id.clone(),
+        winget_id: id.to_string(),
+        line,
+        name: None,
+        publisher: None,
+        version: None,
+        problems: Vec::new(),
+    };
+    let mut problem = |kind, message| report.problems.push(Problem { kind, message });
+
+    let shown = match show(id, store, None) {
+        Ok(Some(shown)) => shown,
+        Ok(None) => {
+            problem("not-found", format!("{id} not found in winget"));
+            return report;
+        }
+        Err(e) => {
+            problem("winget-failed", e);
+            return report;
+        }
+    };
+
+    if !shown.id.is_empty() && shown.id != id {
+        problem("id-changed", format!("winget lists {id} as {}", shown.id));
+    }
+    if let Some(ref publisher) = pkg.publisher
+        && !shown.publisher.is_empty()
+        && !same_publisher(&shown.publisher, publisher)
+    {
+        problem(
+            "publisher-changed",
+            format!(
+                "publisher is '{}', catalog says '{publisher}'",
+                shown.publisher
+            ),
+        );
+    }
+
+    // Store products are installed for the machine; winget has no per-arch view
+    if !store {
+        let arches: Vec<&str> = if pkg.arch.is_empty() {
+            DEFAULT_ARCHES.to_vec()
+        } else {
+            pkg.arch.iter().map(String::as_str).collect()
+        };
+        for arch in arches {
+            match show(id, store, Some(arch)) {
+                Ok(Some(_)) => {}
+                Ok(None) if pkg.arch.is_empty() => problem(
+                    "missing-installer",
+                    format!("no {arch} installer; declare the supported `arch`"),
+                ),
+                Ok(None) => problem("missing-installer", format!("no {arch} installer")),
+                Err(e) => problem("winget-failed", e),
+            }
+        }
+    }
+
+    report.name = Some(shown.name);
+    report.publisher = Some(shown.publisher).filter(|p| !p.is_empty());
+    report.version = Some(shown.version).filter(|v| !v.is_empty());
+    report
+}
+
+/// Publisher names match if one contains the other, ignoring case, so
+/// "Microsoft" matches winget's "Microsoft Corporation".
+fn same_publisher(a: &str, b: &str) -> bool {
+    let (a, b) = (a.to_lowercase(), b.to_lowercase());
+    a.contains(&b) || b.contains(&a)
+}
+
+/// 1-based line number of a byte offset in `raw`.
+fn line_of(raw: &str, offset: usize) -> usize {
+    raw[..offset.min(raw.len())].matches('\n').count() + 1
+}
+
+fn main() -> ExitCode {
+    let mut path = "packages.toml".to_string();
+    let mut json_path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => match args.next() {
+                Some(p) => json_path = Some(p),
+                None => {
+                    eprintln!("error: --json needs a file path");
+                    return ExitCode::FAILURE;
+                }
+            },
+            _ => path = arg,
+        }
+    }
+    let winget = std::env::var("WINGET").unwrap_or_else(|_| "winget".into());
+
+    let raw = match fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(e) => {
+            eprintln!("error: failed to read {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let file: CatalogFile = match toml::from_str(&raw) {
+        Ok(file) => file,
+        Err(e) => {
+            let line = e.span().map(|s| line_of(&raw, s.start)).unwrap_or(0);
+            eprintln!("{path}:{line}: {}", e.message());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut reports = Vec::new();
+    let mut skipped = 0;
+    for spanned in &file.packages {
+        let pkg = spanned.get_ref();
+        let Some((id, store)) = pkg.winget_id() else {
+            skipped += 1;
+            continue;
+        };
+        eprintln!("Checking {id}...");
+        let line = line_of(&raw, spanned.span().start);
+        let show = |id: &str, store, arch: Option<&str>| winget_show(&winget, id, store, arch);
+        reports.push(check(show, pkg, line, id, store));
+    }
+
+    let mut problems = 0;
+    for report in &reports {
+        for p in &report.problems {
+            eprintln!("{path}:{}: [{}] {}", report.line, report.id, p.message);
+            problems += 1;
+        }
+    }
+
+    if let Some(json_path) = json_path {
+        let report = Report {
+            checked: reports.len(),
+            skipped,
+            problems,
+            packages: reports,
+        };
+        let json = serde_json::to_string_pretty(&report).expect("report should serialize");
+        if let Err(e) = fs::write(&json_path, json + "\n") {
+            eprintln!("error: failed to write {json_path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if problems == 0 {
+        println!("{path}: all winget packages resolve");
+        ExitCode::SUCCESS
+    } else {
+        eprintln!("{path}: {problems} problem(s) found");
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(arch: &[&str]) -> Package {
+        Package {
+            id: "git".into(),
+            winget_id: Some("Git.Git".into()),
+            installer: None,
+            publisher: Some("Git".into()),
+            arch: arch.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    fn git(_: &str, _: bool, arch: Option<&str>) -> Result<Option<Shown>, String> {
+        // Only an x64 installer
+        if arch.is_some_and(|a| a != "x64") {
+            return Ok(None);
+        }
+        Ok(Some(Shown {
+            name: "Git".into(),
+            id: "Git.Git".into(),
+            publisher: "The Git Development Community".into(),
+            version: "2.47.0".into(),
+        }))
+    }
+
+    fn kinds(report: &PackageReport) -> Vec<&str> {
+        report.problems.iter().map(|p| p.kind).collect()
+    }
+
+    #[test]
+    fn found_package_is_reported_with_winget_details() {
+        let report = check(git, &package(&["x64"]), 1, "Git.Git", false);
+        assert!(report.problems.is_empty());
+        assert_eq!(report.version.as_deref(), Some("2.47.0"));
+
+        // Without `arch`, x64 and arm64 are both expected
+        let report = check(git, &package(&[]), 1, "Git.Git", false);
+        assert_eq!(kinds(&report), ["missing-installer"]);
+    }
+
+    #[test]
+    fn unknown_id_is_not_found() {
+        let report = check(|_, _, _| Ok(None), &package(&[]), 1, "Git.Git", false);
+        assert_eq!(kinds(&report), ["not-found"]);
+        assert_eq!(report.name, None);
+    }
+
+    #[test]
+    fn missing_winget_is_reported() {
+        let missing = |_: &str, _, _: Option<&str>| Err("failed to run winget: not found".into());
+        let report = check(missing, &package(&[]), 1, "Git.Git", false);
+        assert_eq!(kinds(&report), ["winget-failed"]);
+    }
+}
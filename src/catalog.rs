@@ -1,9 +1,18 @@
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::catalog_merge;
+pub use crate::catalog_types::{Category, Package, VersionPin, WingetOverrides, compare_versions};
+use crate::detect::Detect;
+use crate::installer::{Backend, Installer};
+use crate::post_install::PostInstall;
 use crate::profile::Profile;
+use crate::search::{Item, Match, Query};
+use crate::settings::Architecture;
+use crate::system::SystemInfo;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CatalogSource {
@@ -24,114 +33,1350 @@ impl CatalogSource {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct Package {
-    pub id: String,
-    pub name: String,
-    pub description: String,
-    pub category: String,
-    pub winget_id: Option<String>,
-    #[serde(default)]
-    pub profiles: Vec<String>,
-    pub post_install: Option<String>,
-    pub install_command: Option<String>,
-    /// Precomputed `name.to_lowercase()` for search filtering.
-    #[serde(skip)]
-    pub name_lower: String,
-    /// Precomputed `description.to_lowercase()` for search filtering.
-    #[serde(skip)]
-    pub desc_lower: String,
-    /// Precomputed `winget_id` lowercased, for installed-package lookups.
-    #[serde(skip)]
-    pub winget_id_lower: Option<String>,
-}
+/// Category of packages added from winget search, listed after the
+/// catalog's own.
+const AD_HOC_CATEGORY: &str = "winget-search";
 
 impl Package {
+    /// A package for a `winget search` result that isn't in the catalog. Its
+    /// ID is `winget:<id>` (or `msstore:<id>` for the Store), so a selection
+    /// with it can be exported and imported like catalog packages.
+    pub fn ad_hoc(winget_id: &str, name: &str, source: &str) -> Self {
+        let (prefix, installer) = match source {
+            "msstore" => (
+                "msstore",
+                Installer::Msstore {
+                    id: winget_id.into(),
+                },
+            ),
+            _ => (
+                "winget",
+                Installer::Winget {
+                    id: winget_id.into(),
+                },
+            ),
+        };
+        let mut pkg = Package {
+            id: format!("{prefix}:{winget_id}"),
+            name: name.to_string(),
+            description: format!("{winget_id} from winget search"),
+            category: AD_HOC_CATEGORY.into(),
+            homepage: None,
+            license: None,
+            publisher: None,
+            icon: None,
+            notes: None,
+            winget_id: None,
+            version: None,
+            profiles: Vec::new(),
+            post_install: None,
+            install_command: None,
+            uninstall_command: None,
+            installer: Some(installer),
+            depends_on: Vec::new(),
+            tags: Vec::new(),
+            winget: WingetOverrides::default(),
+            arch: Vec::new(),
+            min_os_build: None,
+            max_os_build: None,
+            detect: None,
+            installed_key: None,
+        };
+        prepare_packages(std::slice::from_mut(&mut pkg));
+        pkg
+    }
+
+    /// The ad-hoc package for an ID like `winget:Git.Git`, e.g. from an
+    /// imported selection; `None` for anything else. Its name is the ID,
+    /// since the search result isn't at hand.
+    pub fn from_ad_hoc_id(id: &str) -> Option<Self> {
+        let (source, winget_id) = id.split_once(':')?;
+        let valid = matches!(source, "winget" | "msstore")
+            && !winget_id.is_empty()
+            && !winget_id.contains(char::is_whitespace);
+        valid.then(|| Self::ad_hoc(winget_id, winget_id, source))
+    }
+
+    /// Added from winget search rather than listed in the catalog.
+    pub fn is_ad_hoc(&self) -> bool {
+        self.category == AD_HOC_CATEGORY
+    }
+
     /// True if this package opens a browser URL instead of silently installing.
     pub fn is_browser_download(&self) -> bool {
-        self.install_command
-            .as_deref()
-            .is_some_and(|c| c.starts_with("start http"))
+        matches!(
+            self.installer,
+            Some(Installer::Command { ref command }) if command.starts_with("start http")
+        )
+    }
+
+    /// Lucide icon for the package, if it declares a known icon name.
+    pub fn icon(&self) -> Option<char> {
+        let name = self.icon.as_deref()?;
+        lucide_icons::Icon::try_from(name).ok().map(char::from)
+    }
+
+    /// Program and arguments that remove this package, or `None` if there
+    /// is no way to.
+    pub fn uninstaller(&self, winget_args: &[String]) -> Option<(String, Vec<String>)> {
+        match self.uninstall_command {
+            Some(ref command) => Some(("cmd".into(), vec!["/C".into(), command.clone()])),
+            None => self.installer.as_ref()?.uninstall_command(winget_args),
+        }
+    }
+
+    /// Why this package can't be installed on `system`, or `None` if it can.
+    /// Properties that couldn't be detected are not checked.
+    pub fn incompatibility(&self, system: &SystemInfo) -> Option<String> {
+        if self.installer == Some(Installer::Unsupported)
+            || self
+                .post_install
+                .as_ref()
+                .is_some_and(PostInstall::is_unsupported)
+        {
+            return Some("Needs a newer version of Provision".into());
+        }
+        if let Some(arch) = system.arch
+            && !self.arch.is_empty()
+            && !self.arch.contains(&arch)
+        {
+            let supported: Vec<String> = self.arch.iter().map(ToString::to_string).collect();
+            return Some(format!(
+                "Not available for {arch} (supports {})",
+                supported.join(", ")
+            ));
+        }
+        if let Some(build) = system.os_build {
+            if let Some(min) = self.min_os_build
+                && build < min
+            {
+                return Some(format!(
+                    "Requires Windows build {min} or newer (this PC: {build})"
+                ));
+            }
+            if let Some(max) = self.max_os_build
+                && build > max
+            {
+                return Some(format!(
+                    "Requires Windows build {max} or older (this PC: {build})"
+                ));
+            }
+        }
+        None
+    }
+
+    /// Fuzzy-match this package's name, description, winget ID and tags.
+    pub fn search_match(&self, query: &Query) -> Option<Match> {
+        crate::search::match_item(
+            query,
+            &Item {
+                name: &self.name,
+                description: &self.description,
+                id: self.installer.as_ref().and_then(Installer::package_name),
+                tags: &self.tags,
+            },
+        )
     }
 }
 
-#[derive(Deserialize)]
+/// The parsed package catalog: packages plus their category and profile definitions.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    pub packages: Vec<Package>,
+    /// Declared categories, sorted by `order`.
+    pub categories: Vec<Category>,
+    /// Profiles in declaration order, as shown on the home screen.
+    pub profiles: Vec<Profile>,
+    /// True if the user's `custom.toml` was merged into this catalog.
+    pub has_overlay: bool,
+    /// Non-fatal problems found while loading, shown on the home screen.
+    pub warnings: Vec<String>,
+}
+
+impl Catalog {
+    pub fn profile(&self, slug: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.slug == slug)
+    }
+
+    /// Add a package from winget search, under a "From winget" category at
+    /// the end. Does nothing if the catalog already has it, under its own ID
+    /// or as a regular package.
+    pub fn add_ad_hoc(&mut self, pkg: Package) {
+        if self
+            .packages
+            .iter()
+            .any(|p| p.id == pkg.id || p.installed_key == pkg.installed_key)
+        {
+            return;
+        }
+        if !self.categories.iter().any(|c| c.slug == AD_HOC_CATEGORY) {
+            self.categories.push(Category {
+                slug: AD_HOC_CATEGORY.into(),
+                name: "From winget".into(),
+                icon: Some("search".into()),
+                order: i32::MAX,
+            });
+        }
+        self.packages.push(pkg);
+    }
+
+    /// Human-readable display name for a category slug (the slug itself if undeclared).
+    pub fn category_name<'a>(&'a self, slug: &'a str) -> &'a str {
+        self.categories
+            .iter()
+            .find(|c| c.slug == slug)
+            .map_or(slug, |c| c.name.as_str())
+    }
+
+    /// Install backends used by packages, in order of first use.
+    pub fn backends(&self) -> Vec<Backend> {
+        let mut backends = Vec::new();
+        for backend in self
+            .packages
+            .iter()
+            .filter_map(|p| p.installer.as_ref().map(Installer::backend))
+        {
+            if !backends.contains(&backend) {
+                backends.push(backend);
+            }
+        }
+        backends
+    }
+
+    /// Detect rules to evaluate during the installed-package scan, keyed
+    /// like the installed-package map. Rules on packages their installer
+    /// can already list are ignored.
+    pub fn detect_rules(&self) -> Vec<(String, Detect)> {
+        self.packages
+            .iter()
+            .filter_map(|p| {
+                let key = p.installed_key.clone()?;
+                let detect = p.detect.clone()?;
+                (key == detect_key(&p.id)).then_some((key, detect))
+            })
+            .collect()
+    }
+
+    /// All tags used by packages, sorted alphabetically.
+    pub fn tags(&self) -> Vec<&str> {
+        let mut tags: Vec<&str> = self
+            .packages
+            .iter()
+            .flat_map(|p| p.tags.iter().map(String::as_str))
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+        tags
+    }
+
+    /// Lucide icon for a category, if it declares a known icon name.
+    pub fn category_icon(&self, slug: &str) -> Option<char> {
+        let name = self
+            .categories
+            .iter()
+            .find(|c| c.slug == slug)?
+            .icon
+            .as_deref()?;
+        lucide_icons::Icon::try_from(name).ok().map(char::from)
+    }
+
+    /// Category slugs that have packages: declared categories in `order`,
+    /// then any undeclared ones in package declaration order.
+    pub fn categories(&self) -> Vec<String> {
+        let used: HashSet<&str> = self.packages.iter().map(|p| p.category.as_str()).collect();
+        let mut result: Vec<String> = self
+            .categories
+            .iter()
+            .filter(|c| used.contains(c.slug.as_str()))
+            .map(|c| c.slug.clone())
+            .collect();
+        for pkg in &self.packages {
+            if !result.contains(&pkg.category) {
+                result.push(pkg.category.clone());
+            }
+        }
+        result
+    }
+}
+
+/// `packages.toml`: categories, profiles, and packages.
+#[derive(Clone, JsonSchema)]
 struct CatalogFile {
+    #[serde(default)]
+    categories: Vec<Category>,
+    #[serde(default)]
+    profiles: Vec<Profile>,
     packages: Vec<Package>,
 }
 
-/// Populate precomputed lowercase fields after deserialization.
+/// Catalog schema this build understands, as `(major, minor)`. A catalog with
+/// another major version is refused; a newer minor version only adds fields,
+/// which older builds ignore. Changing a field's type bumps the major version
+/// (2.0: `post_install` became a list of steps).
+const SCHEMA_VERSION: (u32, u32) = (2, 0);
+
+/// `packages.toml` as read, before packages are checked one by one.
+#[derive(Deserialize)]
+struct RawCatalogFile {
+    #[serde(default)]
+    categories: Vec<Category>,
+    #[serde(default)]
+    profiles: Vec<Profile>,
+    packages: Vec<toml::Value>,
+}
+
+impl CatalogFile {
+    /// Parse a catalog. A package this build can't read (e.g. a value type it
+    /// doesn't know yet) is skipped along with its dependents, rather than
+    /// failing the whole catalog; each skipped package gets a warning.
+    fn parse(raw: &str) -> Result<(Self, Vec<String>), String> {
+        let file: RawCatalogFile = toml::from_str(raw).map_err(|e| format!("parse: {e}"))?;
+
+        let mut warnings = Vec::new();
+        let mut skipped = HashSet::new();
+        let mut packages = Vec::new();
+        for (i, value) in file.packages.into_iter().enumerate() {
+            let id = match value.get("id").and_then(toml::Value::as_str) {
+                Some(id) => id.to_string(),
+                None => format!("#{}", i + 1),
+            };
+            match Package::deserialize(value) {
+                Ok(pkg) => packages.push(pkg),
+                Err(e) => {
+                    warnings.push(format!("Skipped package '{id}': {}", e.message()));
+                    skipped.insert(id);
+                }
+            }
+        }
+
+        // Drop packages that depend on a skipped one, transitively
+        loop {
+            let dependents: Vec<String> = packages
+                .iter()
+                .filter_map(|p: &Package| {
+                    let dep = p.depends_on.iter().find(|d| skipped.contains(*d))?;
+                    Some(format!(
+                        "Skipped package '{}': depends on skipped package '{dep}'",
+                        p.id
+                    ))
+                })
+                .collect();
+            if dependents.is_empty() {
+                break;
+            }
+            warnings.extend(dependents);
+            packages.retain(|p| {
+                let skip = p.depends_on.iter().any(|d| skipped.contains(d));
+                if skip {
+                    skipped.insert(p.id.clone());
+                }
+                !skip
+            });
+        }
+
+        let file = CatalogFile {
+            categories: file.categories,
+            profiles: file.profiles,
+            packages,
+        };
+        Ok((file, warnings))
+    }
+}
+
+/// Top-level catalog keys, read before the rest of the file so an
+/// incompatible catalog is reported as such rather than as a parse error.
+#[derive(Deserialize, JsonSchema)]
+struct CatalogHeader {
+    /// `"major.minor"`; catalogs without it are treated as 1.0.
+    schema_version: Option<String>,
+    /// Oldest Provision release the catalog is meant for.
+    min_app_version: Option<String>,
+}
+
+/// The user's `custom.toml`, merged over the embedded or remote catalog.
+/// Categories and profiles replace entries with the same slug or are added.
+#[derive(Deserialize)]
+struct OverlayFile {
+    #[serde(default)]
+    categories: Vec<Category>,
+    #[serde(default)]
+    profiles: Vec<Profile>,
+    #[serde(default)]
+    packages: Vec<PackageOverride>,
+}
+
+/// A package entry in `custom.toml`. Set fields override the catalog package
+/// with the same `id`; unknown ids add a new package, which then needs at
+/// least `name`, `description` and `category`.
+#[derive(Deserialize)]
+struct PackageOverride {
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+    category: Option<String>,
+    homepage: Option<String>,
+    license: Option<String>,
+    publisher: Option<String>,
+    icon: Option<String>,
+    notes: Option<String>,
+    winget_id: Option<String>,
+    profiles: Option<Vec<String>>,
+    post_install: Option<PostInstall>,
+    install_command: Option<String>,
+    uninstall_command: Option<String>,
+    installer: Option<Installer>,
+    depends_on: Option<Vec<String>>,
+    tags: Option<Vec<String>>,
+    winget: Option<WingetOverrides>,
+    version: Option<VersionPin>,
+    arch: Option<Vec<Architecture>>,
+    min_os_build: Option<u32>,
+    max_os_build: Option<u32>,
+    detect: Option<Detect>,
+    /// Hide this package from the catalog.
+    #[serde(default)]
+    disabled: bool,
+}
+
+impl PackageOverride {
+    fn apply(self, pkg: &mut Package) {
+        if let Some(v) = self.name {
+            pkg.name = v;
+        }
+        if let Some(v) = self.description {
+            pkg.description = v;
+        }
+        if let Some(v) = self.category {
+            pkg.category = v;
+        }
+        if let Some(v) = self.homepage {
+            pkg.homepage = Some(v);
+        }
+        if let Some(v) = self.license {
+            pkg.license = Some(v);
+        }
+        if let Some(v) = self.publisher {
+            pkg.publisher = Some(v);
+        }
+        if let Some(v) = self.icon {
+            pkg.icon = Some(v);
+        }
+        if let Some(v) = self.notes {
+            pkg.notes = Some(v);
+        }
+        if let Some(v) = self.profiles {
+            pkg.profiles = v;
+        }
+        if let Some(v) = self.depends_on {
+            pkg.depends_on = v;
+        }
+        if let Some(v) = self.tags {
+            pkg.tags = v;
+        }
+        if let Some(v) = self.winget {
+            pkg.winget = v;
+        }
+        if let Some(v) = self.version {
+            pkg.version = Some(v);
+        }
+        if let Some(v) = self.arch {
+            pkg.arch = v;
+        }
+        if let Some(v) = self.min_os_build {
+            pkg.min_os_build = Some(v);
+        }
+        if let Some(v) = self.max_os_build {
+            pkg.max_os_build = Some(v);
+        }
+        // A new winget_id or install_command replaces the install method;
+        // install_command would otherwise still win over the new winget_id
+        if self.winget_id.is_some() {
+            pkg.winget_id = self.winget_id;
+            pkg.install_command = None;
+            pkg.installer = None;
+        }
+        if self.post_install.is_some() {
+            pkg.post_install = self.post_install;
+        }
+        // winget_id is also what pins, options and upgrades go by, so it
+        // can't outlive the install method it came with
+        if self.install_command.is_some() {
+            pkg.install_command = self.install_command;
+            pkg.installer = None;
+            pkg.winget_id = None;
+        }
+        if self.installer.is_some() {
+            // prepare_packages takes it back from a winget or Store installer
+            pkg.installer = self.installer;
+            pkg.winget_id = None;
+        }
+        if self.uninstall_command.is_some() {
+            pkg.uninstall_command = self.uninstall_command;
+        }
+        if self.detect.is_some() {
+            pkg.detect = self.detect;
+        }
+    }
+
+    fn into_package(self) -> Result<Package, String> {
+        let missing = |field: &str| format!("new package '{}' has no {field}", self.id);
+        Ok(Package {
+            name: self.name.clone().ok_or_else(|| missing("name"))?,
+            description: self
+                .description
+                .clone()
+                .ok_or_else(|| missing("description"))?,
+            category: self.category.clone().ok_or_else(|| missing("category"))?,
+            id: self.id,
+            homepage: self.homepage,
+            license: self.license,
+            publisher: self.publisher,
+            icon: self.icon,
+            notes: self.notes,
+            winget_id: self.winget_id,
+            profiles: self.profiles.unwrap_or_default(),
+            post_install: self.post_install,
+            install_command: self.install_command,
+            uninstall_command: self.uninstall_command,
+            installer: self.installer,
+            depends_on: self.depends_on.unwrap_or_default(),
+            tags: self.tags.unwrap_or_default(),
+            winget: self.winget.unwrap_or_default(),
+            version: self.version,
+            arch: self.arch.unwrap_or_default(),
+            min_os_build: self.min_os_build,
+            max_os_build: self.max_os_build,
+            detect: self.detect,
+            installed_key: None,
+        })
+    }
+}
+
+/// Merge a parsed overlay into a catalog file.
+fn apply_overlay(mut file: CatalogFile, overlay: OverlayFile) -> Result<CatalogFile, String> {
+    for cat in overlay.categories {
+        match file.categories.iter_mut().find(|c| c.slug == cat.slug) {
+            Some(existing) => *existing = cat,
+            None => file.categories.push(cat),
+        }
+    }
+
+    for profile in overlay.profiles {
+        match file.profiles.iter_mut().find(|p| p.slug == profile.slug) {
+            Some(existing) => *existing = profile,
+            None => file.profiles.push(profile),
+        }
+    }
+
+    let mut disabled = HashSet::new();
+    for entry in overlay.packages {
+        if entry.disabled {
+            disabled.insert(entry.id);
+            continue;
+        }
+        match file.packages.iter_mut().find(|p| p.id == entry.id) {
+            Some(pkg) => entry.apply(pkg),
+            None => file.packages.push(entry.into_package()?),
+        }
+    }
+
+    // Hidden packages are no longer pulled in as dependencies either.
+    file.packages.retain(|p| !disabled.contains(&p.id));
+    for pkg in &mut file.packages {
+        pkg.depends_on.retain(|d| !disabled.contains(d));
+    }
+
+    Ok(file)
+}
+
+/// Installed-map key for a package found by its `detect` rule.
+fn detect_key(id: &str) -> String {
+    format!("detect:{id}")
+}
+
+/// Resolve each package's installer and populate precomputed fields after
+/// deserialization.
 fn prepare_packages(packages: &mut [Package]) {
     for pkg in packages {
-        pkg.name_lower = pkg.name.to_lowercase();
-        pkg.desc_lower = pkg.description.to_lowercase();
-        pkg.winget_id_lower = pkg.winget_id.as_ref().map(|id| id.to_lowercase());
+        if pkg.installer.is_none() {
+            pkg.installer = match (&pkg.install_command, &pkg.winget_id) {
+                (Some(command), _) => Some(Installer::Command {
+                    command: command.clone(),
+                }),
+                (None, Some(id)) => Some(Installer::Winget { id: id.clone() }),
+                (None, None) => None,
+            };
+        }
+        // Winget features (version pins, options, upgrades) key off winget_id
+        if let Some(Installer::Winget { id } | Installer::Msstore { id }) = &pkg.installer
+            && pkg.winget_id.is_none()
+        {
+            pkg.winget_id = Some(id.clone());
+        }
+        pkg.installed_key = pkg
+            .installer
+            .as_ref()
+            .and_then(Installer::installed_key)
+            .or_else(|| pkg.detect.as_ref().map(|_| detect_key(&pkg.id)));
     }
 }
 
-pub fn load_catalog() -> Vec<Package> {
-    let raw = include_str!("../packages.toml");
-    parse_catalog_toml(raw).expect("embedded packages.toml should be valid")
+pub fn load_catalog() -> Catalog {
+    // packages.toml, or packages/ merged by build.rs
+    let raw = include_str!(concat!(env!("OUT_DIR"), "/packages.toml"));
+    let overlay = overlay_path().and_then(|path| std::fs::read_to_string(path).ok());
+    parse_catalog_toml(raw, overlay.as_deref()).expect("embedded packages.toml should be valid")
+}
+
+/// The user's overlay catalog, next to the cached catalog.
+const OVERLAY_FILE: &str = "custom.toml";
+/// The last verified remote catalog, in the cache folder.
+const CACHE_FILE: &str = "packages.toml";
+
+/// The user's overlay catalog: `%APPDATA%\provision\custom.toml`
+fn overlay_path() -> Option<PathBuf> {
+    dirs_cache_dir().ok().map(|d| d.join(OVERLAY_FILE))
+}
+
+/// Read the user's overlay catalog from `cache_dir`, if there is one.
+async fn read_overlay(cache_dir: &Path) -> Option<String> {
+    tokio::fs::read_to_string(cache_dir.join(OVERLAY_FILE))
+        .await
+        .ok()
 }
 
-const REMOTE_URL: &str =
+/// Upstream catalog, the default (and initially only) catalog source.
+pub const DEFAULT_CATALOG_URL: &str =
     "https://raw.githubusercontent.com/Reuzehagel/provision/main/packages.toml";
+/// How long a cached catalog is trusted before it is revalidated with the server.
 const CACHE_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
 const FETCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
-/// Try to load a fresh catalog from the local cache or GitHub.
+/// How downloaded catalogs are checked before they are used.
+enum CatalogSigning {
+    /// Catalogs must carry a valid signature from this minisign public key.
+    #[allow(dead_code)] // Until a key is published
+    Required(&'static str),
+    /// Opt-out for builds without a key: catalogs load without a signature
+    /// check, and each one carries `UNSIGNED_WARNING`.
+    Unsigned,
+}
+
+impl CatalogSigning {
+    fn key(&self) -> Option<&'static str> {
+        match self {
+            Self::Required(key) => Some(key),
+            Self::Unsigned => None,
+        }
+    }
+}
+
+/// This build's catalog signing.
 ///
-/// Returns `Ok(packages)` on success, `Err(reason)` on any failure.
-/// The caller should silently fall back to the embedded catalog on error.
-pub async fn fetch_remote_catalog(dry_run: bool) -> Result<(Vec<Package>, CatalogSource), String> {
-    if dry_run {
-        return Err("skipped in dry-run mode".into());
+/// No key has been published yet, so this build opts out of signature checks.
+/// The maintainer generates the key pair with `just catalog-key`, sets this to
+/// `CatalogSigning::Required("RW...")` with the public key (the second line of
+/// `catalog.pub`), and signs packages.toml with `just sign-packages`.
+const CATALOG_SIGNING: CatalogSigning = CatalogSigning::Unsigned;
+const UNSIGNED_WARNING: &str =
+    "Catalog loaded without a signature check: this build has no catalog signing key";
+
+/// Whether this build only accepts signed catalogs.
+pub fn requires_signatures() -> bool {
+    CATALOG_SIGNING.key().is_some()
+}
+
+/// A catalog source to try, and the key it is trusted with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+    pub location: String,
+    /// Minisign public key this source's catalogs must be signed with,
+    /// instead of the one built into the app (e.g. an internal mirror that
+    /// signs its own catalog).
+    pub public_key: Option<String>,
+}
+
+impl Source {
+    /// The key to check this source's catalogs with, if any.
+    fn key<'a>(&'a self, built_in: Option<&'a str>) -> Option<&'a str> {
+        self.public_key.as_deref().or(built_in)
     }
+}
 
-    let cache_dir = dirs_cache_dir()?;
-    let cache_path = cache_dir.join("packages.toml");
+/// Check that `key` is a minisign public key (`RW...`).
+pub fn check_public_key(key: &str) -> Result<(), String> {
+    minisign_verify::PublicKey::from_base64(key.trim())
+        .map(|_| ())
+        .map_err(|e| format!("public key: {e}"))
+}
 
-    // If cache exists and is fresh, use it
-    if let Ok(meta) = tokio::fs::metadata(&cache_path).await
-        && let Ok(modified) = meta.modified()
-        && modified.elapsed().unwrap_or(CACHE_MAX_AGE) < CACHE_MAX_AGE
-    {
-        let raw = tokio::fs::read_to_string(&cache_path)
+/// Why a remote catalog could not be used.
+#[derive(Debug, Clone)]
+pub enum CatalogError {
+    /// Offline, timed out, or otherwise unreachable. Fall back silently.
+    Unavailable(String),
+    /// Downloaded but refused (e.g. bad signature). Fall back and warn the user.
+    Rejected(String),
+}
+
+/// Outcome of revalidating the cached catalog against the server.
+#[derive(Debug, Clone)]
+pub enum CatalogRefresh {
+    /// The cache was checked recently enough that no request was made.
+    Fresh,
+    /// The server confirmed the cached catalog is current (HTTP 304).
+    NotModified,
+    /// A newer catalog was downloaded, verified, and cached.
+    Updated(Catalog),
+}
+
+/// HTTP validators and check time stored next to the cached catalog.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheMeta {
+    /// Catalog source the cache was loaded from; validators only apply to it.
+    source: Option<String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// Unix timestamp (seconds) of the last successful check with the server.
+    #[serde(default)]
+    checked_at: u64,
+}
+
+impl CacheMeta {
+    fn is_fresh(&self) -> bool {
+        unix_now().saturating_sub(self.checked_at) < CACHE_MAX_AGE.as_secs()
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Where a catalog source points.
+enum SourceKind {
+    Http(String),
+    Local(PathBuf),
+}
+
+/// Classify a source location: `http(s)://` URLs are fetched, `file://` URLs
+/// and plain local or UNC paths are read from disk.
+fn parse_source(location: &str) -> Result<SourceKind, String> {
+    let location = location.trim();
+    if location.starts_with("https://") || location.starts_with("http://") {
+        return Ok(SourceKind::Http(location.to_string()));
+    }
+    if location.starts_with("file:") {
+        return reqwest::Url::parse(location)
+            .ok()
+            .and_then(|url| url.to_file_path().ok())
+            .map(SourceKind::Local)
+            .ok_or_else(|| format!("invalid file URL '{location}'"));
+    }
+    if location.is_empty() || location.contains("://") {
+        return Err(format!("unsupported source '{location}'"));
+    }
+    Ok(SourceKind::Local(PathBuf::from(location)))
+}
+
+/// Check a detached minisign signature against `key`, the source's own key or
+/// the one in `CATALOG_SIGNING`.
+fn verify_signature(key: &str, raw: &str, signature: &str) -> Result<(), String> {
+    let key =
+        minisign_verify::PublicKey::from_base64(key).map_err(|e| format!("public key: {e}"))?;
+    let signature =
+        minisign_verify::Signature::decode(signature).map_err(|e| format!("signature: {e}"))?;
+    key.verify(raw.as_bytes(), &signature, false)
+        .map_err(|e| format!("signature: {e}"))
+}
+
+/// Load the cached catalog from `cache_dir`, re-checking its signature if
+/// there is a `key`.
+async fn read_cache(cache_dir: &Path, key: Option<&str>) -> Result<Catalog, String> {
+    let cache_path = cache_dir.join(CACHE_FILE);
+    let raw = tokio::fs::read_to_string(&cache_path)
+        .await
+        .map_err(|e| format!("cache read: {e}"))?;
+    if let Some(key) = key {
+        let signature = tokio::fs::read_to_string(sibling_path(&cache_path, "minisig"))
             .await
-            .map_err(|e| format!("cache read: {e}"))?;
-        return parse_catalog_toml(&raw).map(|pkgs| (pkgs, CatalogSource::Cached));
+            .map_err(|e| format!("cache signature read: {e}"))?;
+        verify_signature(key, &raw, &signature)?;
     }
 
-    // Fetch from remote
+    let overlay = read_overlay(cache_dir).await;
+    let mut catalog = parse_catalog_toml(&raw, overlay.as_deref())?;
+    if key.is_none() {
+        catalog.warnings.push(UNSIGNED_WARNING.into());
+    }
+    Ok(catalog)
+}
+
+/// Load the cached catalog, checked with the key of the source it came from,
+/// along with its metadata. A source that is no longer configured falls back
+/// to the `built_in` key.
+async fn read_trusted_cache(
+    cache_dir: &Path,
+    built_in: Option<&str>,
+    sources: &[Source],
+) -> Result<(Catalog, CacheMeta), String> {
+    let meta = read_meta(&cache_dir.join(CACHE_FILE)).await;
+    let key = sources
+        .iter()
+        .find(|s| meta.source.as_ref() == Some(&s.location))
+        .map_or(built_in, |s| s.key(built_in));
+    let catalog = read_cache(cache_dir, key).await?;
+    Ok((catalog, meta))
+}
+
+/// Read the cache metadata. Missing or unreadable metadata counts as never checked.
+async fn read_meta(cache_path: &Path) -> CacheMeta {
+    tokio::fs::read_to_string(sibling_path(cache_path, "meta"))
+        .await
+        .ok()
+        .and_then(|raw| toml::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// Write the cache metadata (best-effort).
+async fn write_meta(cache_path: &Path, meta: &CacheMeta) {
+    if let Ok(raw) = toml::to_string(meta) {
+        let _ = tokio::fs::write(sibling_path(cache_path, "meta"), raw).await;
+    }
+}
+
+/// Write the cached catalog's signature, or remove a stale one if the catalog
+/// wasn't signed (best-effort).
+async fn write_signature(cache_path: &Path, signature: Option<String>) {
+    let path = sibling_path(cache_path, "minisig");
+    let _ = match signature {
+        Some(signature) => tokio::fs::write(path, signature).await,
+        None => tokio::fs::remove_file(path).await,
+    };
+}
+
+/// `packages.toml` + `minisig` -> `packages.toml.minisig`
+fn sibling_path(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    path.with_file_name(name)
+}
+
+/// A catalog read from a source, not yet verified.
+struct Fetched {
+    raw: String,
+    /// Detached minisign signature, if one was asked for.
+    signature: Option<String>,
+    meta: CacheMeta,
+}
+
+/// Download the remote catalog, and its detached signature if `signed`.
+///
+/// Sends the cached validators as `If-None-Match` / `If-Modified-Since` and
+/// returns `None` when the server answers 304 Not Modified.
+async fn download_catalog(
+    url: &str,
+    cached: &CacheMeta,
+    signed: bool,
+) -> Result<Option<Fetched>, String> {
+    use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+
     let client = reqwest::Client::builder()
         .timeout(FETCH_TIMEOUT)
         .build()
         .map_err(|e| format!("http client: {e}"))?;
 
-    let resp = client
-        .get(REMOTE_URL)
+    let mut request = client.get(url);
+    if let Some(ref etag) = cached.etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(ref last_modified) = cached.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+    }
+    let resp = request.send().await.map_err(|e| format!("fetch: {e}"))?;
+    if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    let resp = resp.error_for_status().map_err(|e| format!("fetch: {e}"))?;
+
+    let header = |name| {
+        resp.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let meta = CacheMeta {
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+        checked_at: unix_now(),
+        ..Default::default()
+    };
+    let raw = resp.text().await.map_err(|e| format!("read body: {e}"))?;
+    if !signed {
+        return Ok(Some(Fetched {
+            raw,
+            signature: None,
+            meta,
+        }));
+    }
+
+    let signature = client
+        .get(format!("{url}.minisig"))
         .send()
         .await
         .map_err(|e| format!("fetch: {e}"))?
         .error_for_status()
-        .map_err(|e| format!("fetch: {e}"))?;
+        .map_err(|e| format!("fetch: {e}"))?
+        .text()
+        .await
+        .map_err(|e| format!("read body: {e}"))?;
 
-    let raw = resp.text().await.map_err(|e| format!("read body: {e}"))?;
+    Ok(Some(Fetched {
+        raw,
+        signature: Some(signature),
+        meta,
+    }))
+}
+
+/// Check that a source location is something `refresh_catalog` can read.
+pub fn check_source_location(location: &str) -> Result<(), String> {
+    parse_source(location).map(|_| ())
+}
+
+/// Read a catalog (and its signature, if `signed`) from a local file, or from
+/// `packages.toml` inside a local folder (e.g. a USB stick or a network share).
+/// A folder without `packages.toml` may hold a split `packages/` catalog
+/// instead; its merged text must match the folder's `packages.toml.minisig`.
+async fn read_local_catalog(path: &Path, signed: bool) -> Result<Option<Fetched>, String> {
+    let (path, split) = match tokio::fs::metadata(path).await {
+        Ok(meta) if meta.is_dir() => {
+            let file = path.join("packages.toml");
+            let split = path.join("packages");
+            let is_split = !file.exists() && catalog_merge::is_catalog_dir(&split);
+            (file, is_split.then_some(split))
+        }
+        Ok(_) => (path.to_path_buf(), None),
+        Err(e) => return Err(format!("{}: {e}", path.display())),
+    };
+    let raw = match split {
+        Some(dir) => catalog_merge::merge_dir(&dir)?.text,
+        None => tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| format!("{}: {e}", path.display()))?,
+    };
+    let signature = if signed {
+        let signature = tokio::fs::read_to_string(sibling_path(&path, "minisig"))
+            .await
+            .map_err(|e| format!("{}.minisig: {e}", path.display()))?;
+        Some(signature)
+    } else {
+        None
+    };
+
+    let meta = CacheMeta {
+        checked_at: unix_now(),
+        ..Default::default()
+    };
+    Ok(Some(Fetched {
+        raw,
+        signature,
+        meta,
+    }))
+}
+
+/// Fetch a catalog from one source. `None` means the cached copy is current.
+async fn fetch_source(
+    location: &str,
+    cached: &CacheMeta,
+    signed: bool,
+) -> Result<Option<Fetched>, String> {
+    match parse_source(location)? {
+        SourceKind::Http(url) => download_catalog(&url, cached, signed).await,
+        SourceKind::Local(path) => read_local_catalog(&path, signed).await,
+    }
+}
+
+/// Load the last verified catalog from the local cache, however old.
+///
+/// Shown immediately on startup; `refresh_catalog` then revalidates it in the
+/// background.
+pub async fn load_cached_catalog(
+    sources: Vec<Source>,
+    dry_run: bool,
+) -> Result<(Catalog, CatalogSource), CatalogError> {
+    if dry_run {
+        return Err(CatalogError::Unavailable("skipped in dry-run mode".into()));
+    }
+
+    let cache_dir = dirs_cache_dir().map_err(CatalogError::Unavailable)?;
+    let (catalog, _) = read_trusted_cache(&cache_dir, CATALOG_SIGNING.key(), &sources)
+        .await
+        .map_err(CatalogError::Unavailable)?;
+    Ok((catalog, CatalogSource::Cached))
+}
+
+/// Revalidate the cached catalog against the configured sources.
+///
+/// `sources` are tried in priority order (`PROVISION_CATALOG_URL`, if set,
+/// replaces them, e.g. to point at a local server while testing). The request
+/// is skipped while the cache is younger than `CACHE_MAX_AGE` and came from
+/// the first source, unless `force` is set. Unless this build opted out with
+/// `CatalogSigning::Unsigned`, a catalog must carry a valid signature before
+/// it is cached, from the source's own key if it has one or else the built-in
+/// key; a source that fails verification is skipped in favour of the next one.
+pub async fn refresh_catalog(
+    sources: Vec<Source>,
+    dry_run: bool,
+    force: bool,
+) -> Result<CatalogRefresh, CatalogError> {
+    if dry_run {
+        return Err(CatalogError::Unavailable("skipped in dry-run mode".into()));
+    }
+    let sources = match std::env::var("PROVISION_CATALOG_URL") {
+        Ok(url) => vec![Source {
+            location: url,
+            public_key: None,
+        }],
+        Err(_) => sources,
+    };
+    let cache_dir = dirs_cache_dir().map_err(CatalogError::Unavailable)?;
+    refresh_in(&cache_dir, CATALOG_SIGNING.key(), &sources, force).await
+}
 
-    let packages = parse_catalog_toml(&raw)?;
+/// `refresh_catalog` with the cache folder and built-in public key (if any)
+/// given.
+async fn refresh_in(
+    cache_dir: &Path,
+    built_in: Option<&str>,
+    sources: &[Source],
+    force: bool,
+) -> Result<CatalogRefresh, CatalogError> {
+    let Some(first) = sources.first() else {
+        return Err(CatalogError::Unavailable(
+            "no catalog sources enabled".into(),
+        ));
+    };
+    let cache_path = cache_dir.join(CACHE_FILE);
+
+    // Only trust the validators if the cache they describe is still usable
+    let cache = read_trusted_cache(cache_dir, built_in, sources).await;
+    let has_cache = cache.is_ok();
+    let meta = cache.map(|(_, meta)| meta).unwrap_or_default();
+    if has_cache && !force && meta.is_fresh() && meta.source.as_ref() == Some(&first.location) {
+        return Ok(CatalogRefresh::Fresh);
+    }
+
+    let mut errors = Vec::new();
+    let mut rejected = None;
+    for source in sources {
+        let location = &source.location;
+        let key = source.key(built_in);
+        let validators = if meta.source.as_ref() == Some(location) {
+            meta.clone()
+        } else {
+            CacheMeta::default()
+        };
+        let fetched = match fetch_source(location, &validators, key.is_some()).await {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        let Some(Fetched {
+            raw,
+            signature,
+            meta: mut new_meta,
+        }) = fetched
+        else {
+            write_meta(
+                &cache_path,
+                &CacheMeta {
+                    checked_at: unix_now(),
+                    ..validators
+                },
+            )
+            .await;
+            return Ok(CatalogRefresh::NotModified);
+        };
 
-    // Write to cache (best-effort)
-    let _ = tokio::fs::create_dir_all(&cache_dir).await;
-    let _ = tokio::fs::write(&cache_path, &raw).await;
+        let verified = match (key, &signature) {
+            (Some(key), Some(signature)) => verify_signature(key, &raw, signature),
+            (Some(_), None) => Err("signature: missing".into()),
+            (None, _) => Ok(()),
+        };
+        if let Err(e) = verified.and_then(|()| check_schema(&raw)) {
+            rejected = Some(format!("Catalog from {location} rejected ({e})"));
+            continue;
+        }
+        new_meta.source = Some(location.clone());
+
+        let cached_raw = tokio::fs::read_to_string(&cache_path).await.ok();
+        if has_cache && cached_raw.as_deref() == Some(raw.as_str()) {
+            // The same catalog may be signed with another source's key
+            write_signature(&cache_path, signature).await;
+            write_meta(&cache_path, &new_meta).await;
+            return Ok(CatalogRefresh::NotModified);
+        }
 
-    Ok((packages, CatalogSource::Remote))
+        let overlay = read_overlay(cache_dir).await;
+        let mut catalog = match parse_catalog_toml(&raw, overlay.as_deref()) {
+            Ok(catalog) => catalog,
+            Err(e) => {
+                errors.push(format!("{location}: {e}"));
+                continue;
+            }
+        };
+
+        // Write to cache (best-effort)
+        let _ = tokio::fs::create_dir_all(cache_dir).await;
+        let _ = tokio::fs::write(&cache_path, &raw).await;
+        write_signature(&cache_path, signature).await;
+        write_meta(&cache_path, &new_meta).await;
+
+        if key.is_none() {
+            catalog.warnings.push(UNSIGNED_WARNING.into());
+        }
+        return Ok(CatalogRefresh::Updated(catalog));
+    }
+
+    match rejected {
+        Some(reason) => {
+            let fallback = if has_cache {
+                "keeping last trusted copy"
+            } else {
+                "using built-in catalog"
+            };
+            Err(CatalogError::Rejected(format!("{reason}; {fallback}")))
+        }
+        None => Err(CatalogError::Unavailable(errors.join("; "))),
+    }
+}
+
+/// Parse a catalog and merge the user's overlay over it.
+fn parse_catalog_toml(raw: &str, overlay: Option<&str>) -> Result<Catalog, String> {
+    let schema_warnings = check_schema(raw)?;
+    let mut catalog = parse_with_overlay(raw, overlay)?;
+    catalog.warnings.splice(0..0, schema_warnings);
+    Ok(catalog)
 }
 
-fn parse_catalog_toml(raw: &str) -> Result<Vec<Package>, String> {
-    let file: CatalogFile = toml::from_str(raw).map_err(|e| format!("parse: {e}"))?;
+/// Check the catalog's `schema_version` (1.0 if missing) and `min_app_version`
+/// against this build. Returns warnings for a newer minor schema or an
+/// outdated app, and an error for an older or newer major schema.
+fn check_schema(raw: &str) -> Result<Vec<String>, String> {
+    let header: CatalogHeader = toml::from_str(raw).map_err(|e| format!("parse: {e}"))?;
+    let mut warnings = Vec::new();
+
+    let version = header.schema_version.as_deref().unwrap_or("1.0");
+    let (major, minor) = version
+        .split_once('.')
+        .and_then(|(major, minor)| Some((major.parse::<u32>().ok()?, minor.parse::<u32>().ok()?)))
+        .ok_or_else(|| format!("invalid schema_version '{version}'"))?;
+    let supported = SCHEMA_VERSION.0;
+    if major < supported {
+        return Err(format!(
+            "catalog schema {version} is older than this version of Provision reads \
+             ({supported}.x); the catalog needs updating"
+        ));
+    }
+    if major > supported {
+        return Err(format!(
+            "catalog schema {version} needs a newer version of Provision (this one reads {supported}.x)"
+        ));
+    }
+    if minor > SCHEMA_VERSION.1 {
+        warnings.push(format!(
+            "Catalog schema {version} is newer than this app ({supported}.{}); some entries may be ignored",
+            SCHEMA_VERSION.1
+        ));
+    }
+
+    let app_version = env!("CARGO_PKG_VERSION");
+    if let Some(ref min) = header.min_app_version
+        && parse_app_version(app_version) < parse_app_version(min)
+    {
+        warnings.push(format!(
+            "This catalog is meant for Provision {min} or newer (running {app_version}); please update"
+        ));
+    }
+
+    Ok(warnings)
+}
+
+/// `"1.2.3"` -> `[1, 2, 3]`, padding missing components with zero.
+/// Unparseable components count as zero.
+fn parse_app_version(version: &str) -> [u32; 3] {
+    let mut parts = [0; 3];
+    for (part, component) in parts.iter_mut().zip(version.trim().split('.')) {
+        *part = component.parse().unwrap_or(0);
+    }
+    parts
+}
+
+/// Parse a catalog and merge the user's overlay over it. A broken overlay is
+/// skipped with a warning rather than discarding the whole catalog.
+fn parse_with_overlay(raw: &str, overlay: Option<&str>) -> Result<Catalog, String> {
+    let (file, skipped) = CatalogFile::parse(raw)?;
+
+    let Some(overlay) = overlay else {
+        let mut catalog = build_catalog(file)?;
+        catalog.warnings = skipped;
+        return Ok(catalog);
+    };
+
+    let merged = toml::from_str::<OverlayFile>(overlay)
+        .map_err(|e| format!("parse: {e}"))
+        .and_then(|o| apply_overlay(file.clone(), o))
+        .and_then(build_catalog);
+
+    match merged {
+        Ok(mut catalog) => {
+            catalog.has_overlay = true;
+            catalog.warnings = skipped;
+            Ok(catalog)
+        }
+        Err(e) => {
+            let mut catalog = build_catalog(file)?;
+            catalog.warnings = skipped;
+            catalog.warnings.push(format!("custom.toml ignored: {e}"));
+            Ok(catalog)
+        }
+    }
+}
+
+/// Validate a deserialized catalog file and turn it into a `Catalog`.
+fn build_catalog(file: CatalogFile) -> Result<Catalog, String> {
     let mut packages = file.packages;
+    validate_dependencies(&packages)?;
+    validate_profiles(&file.profiles)?;
     prepare_packages(&mut packages);
-    Ok(packages)
+
+    let mut categories = file.categories;
+    categories.sort_by_key(|c| c.order);
+
+    Ok(Catalog {
+        packages,
+        categories,
+        profiles: file.profiles,
+        ..Default::default()
+    })
+}
+
+/// Require at least one profile and reject unknown or cyclic `extends` chains.
+fn validate_profiles(profiles: &[Profile]) -> Result<(), String> {
+    if profiles.is_empty() {
+        return Err("catalog declares no profiles".into());
+    }
+
+    for profile in profiles {
+        let mut chain = vec![profile.slug.as_str()];
+        let mut current = profile;
+        while let Some(ref parent) = current.extends {
+            if chain.contains(&parent.as_str()) {
+                chain.push(parent);
+                return Err(format!("profile cycle: {}", chain.join(" -> ")));
+            }
+            current = profiles.iter().find(|p| p.slug == *parent).ok_or_else(|| {
+                format!(
+                    "profile '{}' extends unknown profile '{parent}'",
+                    current.slug
+                )
+            })?;
+            chain.push(parent);
+        }
+    }
+    Ok(())
+}
+
+/// Reject dependencies on unknown package IDs and dependency cycles.
+fn validate_dependencies(packages: &[Package]) -> Result<(), String> {
+    let by_id: HashMap<&str, &Package> = packages.iter().map(|p| (p.id.as_str(), p)).collect();
+
+    for pkg in packages {
+        if let Some(dep) = pkg
+            .depends_on
+            .iter()
+            .find(|d| !by_id.contains_key(d.as_str()))
+        {
+            return Err(format!("'{}' depends on unknown package '{dep}'", pkg.id));
+        }
+    }
+
+    // Depth-first search; a package reached again while still on the path is a cycle.
+    fn visit<'a>(
+        id: &'a str,
+        by_id: &HashMap<&'a str, &'a Package>,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Result<(), String> {
+        if done.contains(id) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|p| *p == id) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(id);
+            return Err(format!("dependency cycle: {}", cycle.join(" -> ")));
+        }
+        path.push(id);
+        for dep in &by_id[id].depends_on {
+            visit(dep, by_id, path, done)?;
+        }
+        path.pop();
+        done.insert(id);
+        Ok(())
+    }
+
+    let mut done = HashSet::new();
+    for pkg in packages {
+        visit(&pkg.id, &by_id, &mut Vec::new(), &mut done)?;
+    }
+    Ok(())
+}
+
+/// Transitive dependencies of `ids` that are not themselves in `ids`, in catalog order.
+pub fn missing_dependencies<'a>(catalog: &'a [Package], ids: &HashSet<String>) -> Vec<&'a Package> {
+    let by_id: HashMap<&str, &Package> = catalog.iter().map(|p| (p.id.as_str(), p)).collect();
+    let mut needed: HashSet<&str> = HashSet::new();
+    let mut stack: Vec<&str> = ids.iter().map(String::as_str).collect();
+
+    while let Some(id) = stack.pop() {
+        let Some(pkg) = by_id.get(id) else {
+            continue;
+        };
+        for dep in &pkg.depends_on {
+            if !ids.contains(dep) && needed.insert(dep.as_str()) {
+                stack.push(dep);
+            }
+        }
+    }
+
+    catalog
+        .iter()
+        .filter(|p| needed.contains(p.id.as_str()))
+        .collect()
+}
+
+/// Packages that depend on `id`, directly or through others, in catalog order.
+pub fn dependents<'a>(catalog: &'a [Package], id: &str) -> Vec<&'a Package> {
+    let mut found: HashSet<&str> = HashSet::new();
+    let mut stack = vec![id];
+
+    while let Some(id) = stack.pop() {
+        for pkg in catalog {
+            if pkg.depends_on.iter().any(|d| d == id) && found.insert(pkg.id.as_str()) {
+                stack.push(&pkg.id);
+            }
+        }
+    }
+
+    catalog
+        .iter()
+        .filter(|p| found.contains(p.id.as_str()))
+        .collect()
 }
 
 /// Resolve the app data directory: `%APPDATA%\provision`
@@ -140,48 +1385,61 @@ pub(crate) fn dirs_cache_dir() -> Result<PathBuf, String> {
     Ok(PathBuf::from(appdata).join("provision"))
 }
 
-/// Return the set of package IDs that should be pre-selected for a profile.
-pub fn default_selection(catalog: &[Package], profile: Profile) -> HashSet<String> {
-    let slug = profile.slug();
+/// Return the set of package IDs that should be pre-selected for a profile,
+/// including those of every profile it extends.
+pub fn default_selection(catalog: &Catalog, slug: &str) -> HashSet<String> {
+    let mut slugs = Vec::new();
+    let mut current = catalog.profile(slug);
+    while let Some(profile) = current {
+        slugs.push(profile.slug.as_str());
+        current = profile.extends.as_deref().and_then(|s| catalog.profile(s));
+    }
+
     catalog
+        .packages
         .iter()
-        .filter(|p| p.profiles.iter().any(|s| s == slug))
+        .filter(|p| p.profiles.iter().any(|s| slugs.contains(&s.as_str())))
         .map(|p| p.id.clone())
         .collect()
 }
 
-/// Human-readable display name for a category slug.
-pub fn category_display_name(slug: &str) -> &str {
-    match slug {
-        "browsers" => "Browsers",
-        "communication" => "Communication",
-        "development" => "Development",
-        "documents" => "Documents",
-        "games" => "Games",
-        "multimedia" => "Multimedia",
-        "microsoft-tools" => "Microsoft Tools",
-        "utilities" => "Utilities",
-        "security-privacy" => "Security & Privacy",
-        "design" => "Design",
-        _ => slug,
-    }
+/// A package selection exported from the package picker.
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct SelectionFile {
+    selected: Vec<String>,
 }
 
-/// Ordered unique categories, preserving declaration order from the catalog.
-pub fn categories(catalog: &[Package]) -> Vec<String> {
-    let mut seen = HashSet::new();
-    let mut result = Vec::new();
-    for pkg in catalog {
-        if seen.insert(pkg.category.as_str()) {
-            result.push(pkg.category.clone());
-        }
+/// JSON Schemas for `packages.toml` and exported selection files, as
+/// `(file name, JSON)`. The files in `schemas/` are checked against them by
+/// a test.
+#[cfg(test)]
+fn json_schemas() -> Vec<(&'static str, String)> {
+    // Draft 7 is the newest draft Taplo fully supports
+    let generator = || schemars::generate::SchemaSettings::draft07().into_generator();
+    let mut catalog = generator().into_root_schema_for::<CatalogFile>();
+    // Header keys are read on their own, before the rest of the catalog
+    let header = generator().into_root_schema_for::<CatalogHeader>();
+    if let Some(properties) = catalog
+        .get_mut("properties")
+        .and_then(serde_json::Value::as_object_mut)
+        && let Some(header) = header
+            .get("properties")
+            .and_then(serde_json::Value::as_object)
+    {
+        properties.extend(header.clone());
     }
-    result
-}
+    let selection = generator().into_root_schema_for::<SelectionFile>();
 
-#[derive(Serialize, Deserialize)]
-struct SelectionFile {
-    selected: Vec<String>,
+    [
+        ("catalog.schema.json", catalog),
+        ("selection.schema.json", selection),
+    ]
+    .into_iter()
+    .map(|(name, schema)| {
+        let json = serde_json::to_string_pretty(&schema).expect("schema should serialize");
+        (name, json + "\n")
+    })
+    .collect()
 }
 
 /// Show a Save dialog and write the selection to a TOML file.
@@ -229,7 +1487,7 @@ pub async fn import_selection(valid_ids: HashSet<String>) -> Result<HashSet<Stri
     let imported: HashSet<String> = file
         .selected
         .into_iter()
-        .filter(|id| valid_ids.contains(id))
+        .filter(|id| valid_ids.contains(id) || Package::from_ad_hoc_id(id).is_some())
         .collect();
 
     if imported.is_empty() {
@@ -238,3 +1496,543 @@ pub async fn import_selection(valid_ids: HashSet<String>) -> Result<HashSet<Stri
 
     Ok(imported)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Public half of the throwaway key that signed the fixture catalog.
+    const TEST_PUBLIC_KEY: &str = "RWRXxZDBBuxod21eAPELnv6DZRs4A0QFchtln3ZZyxqV+0Xdqb3LL/fr";
+    /// Another key (different key id), standing in for the built-in one.
+    const OTHER_PUBLIC_KEY: &str = "RWRXxABBBuxod21eAPELnv6DZRs4A0QFchtln3ZZyxqV+0Xdqb3LL/fr";
+    const SIGNED: &str = include_str!("../tests/fixtures/packages.toml");
+    const SIGNATURE: &str = include_str!("../tests/fixtures/packages.toml.minisig");
+    const ETAG: &str = "\"v1\"";
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// An empty cache folder for one test.
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("provision-test-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Serve `body` as `/packages.toml` with `ETAG`, and the fixture
+    /// signature next to it. Returns the catalog URL and the number of 304
+    /// answers so far.
+    fn serve(body: String) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/packages.toml", listener.local_addr().unwrap());
+        let not_modified = Arc::new(AtomicUsize::new(0));
+        let counter = not_modified.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut request = String::new();
+                let mut reader = BufReader::new(&stream);
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                    request.push_str(&line.to_ascii_lowercase());
+                    line.clear();
+                }
+                let ok = |body: &str| {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nETag: {ETAG}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                };
+                let response = if request.starts_with("get /packages.toml.minisig ") {
+                    ok(SIGNATURE)
+                } else if request.contains(&format!("if-none-match: {ETAG}")) {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n".into()
+                } else {
+                    ok(&body)
+                };
+                let _ = (&stream).write_all(response.as_bytes());
+            }
+        });
+        (url, not_modified)
+    }
+
+    fn source(location: &str, public_key: Option<&str>) -> Source {
+        Source {
+            location: location.to_string(),
+            public_key: public_key.map(str::to_string),
+        }
+    }
+
+    fn refresh(dir: &Path, url: &str, force: bool) -> Result<CatalogRefresh, CatalogError> {
+        block_on(refresh_in(
+            dir,
+            Some(TEST_PUBLIC_KEY),
+            &[source(url, None)],
+            force,
+        ))
+    }
+
+    fn cached(dir: &Path) -> String {
+        std::fs::read_to_string(dir.join(CACHE_FILE)).unwrap_or_default()
+    }
+
+    #[test]
+    fn refresh_caches_a_signed_catalog_and_revalidates_it() {
+        let dir = cache_dir("revalidate");
+        let (url, not_modified) = serve(SIGNED.into());
+
+        let Ok(CatalogRefresh::Updated(catalog)) = refresh(&dir, &url, false) else {
+            panic!("expected the signed catalog");
+        };
+        assert_eq!(catalog.packages[0].id, "git");
+        assert_eq!(cached(&dir), SIGNED);
+
+        // A fresh cache isn't checked again unless forced
+        assert!(matches!(
+            refresh(&dir, &url, false),
+            Ok(CatalogRefresh::Fresh)
+        ));
+        assert!(matches!(
+            refresh(&dir, &url, true),
+            Ok(CatalogRefresh::NotModified)
+        ));
+        assert_eq!(not_modified.load(Ordering::SeqCst), 1);
+        assert_eq!(cached(&dir), SIGNED);
+    }
+
+    #[test]
+    fn refresh_rejects_a_bad_signature_and_keeps_the_cache() {
+        let dir = cache_dir("bad-signature");
+        let (url, _) = serve(SIGNED.into());
+        assert!(matches!(
+            refresh(&dir, &url, false),
+            Ok(CatalogRefresh::Updated(_))
+        ));
+
+        let (tampered, _) = serve(format!("{SIGNED}\n# tampered\n"));
+        let Err(CatalogError::Rejected(reason)) = refresh(&dir, &tampered, true) else {
+            panic!("expected the tampered catalog to be rejected");
+        };
+        assert!(reason.contains("keeping last trusted copy"), "{reason}");
+        assert_eq!(cached(&dir), SIGNED);
+        assert!(block_on(read_cache(&dir, Some(TEST_PUBLIC_KEY))).is_ok());
+    }
+
+    #[test]
+    fn refresh_reads_a_file_url() {
+        let dir = cache_dir("file-url");
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/packages.toml");
+        let url = reqwest::Url::from_file_path(fixture).unwrap();
+
+        assert!(matches!(
+            refresh(&dir, url.as_str(), false),
+            Ok(CatalogRefresh::Updated(_))
+        ));
+        assert_eq!(cached(&dir), SIGNED);
+    }
+
+    #[test]
+    fn sources_are_checked_with_their_own_key() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/packages.toml");
+        let file_url = reqwest::Url::from_file_path(&fixture).unwrap();
+        let folder = cache_dir("usb-stick");
+        std::fs::copy(&fixture, folder.join("packages.toml")).unwrap();
+        std::fs::write(folder.join("packages.toml.minisig"), SIGNATURE).unwrap();
+        let folder = folder.to_str().unwrap();
+
+        for (name, location) in [
+            ("own-key-file", file_url.as_str()),
+            ("own-key-folder", folder),
+        ] {
+            let dir = cache_dir(name);
+            // The built-in key didn't sign it
+            let Err(CatalogError::Rejected(_)) = block_on(refresh_in(
+                &dir,
+                Some(OTHER_PUBLIC_KEY),
+                &[source(location, None)],
+                false,
+            )) else {
+                panic!("{location}: expected the built-in key to reject it");
+            };
+
+            // The source's own key did, in signed and unsigned builds alike
+            for built_in in [Some(OTHER_PUBLIC_KEY), None] {
+                let sources = [source(location, Some(TEST_PUBLIC_KEY))];
+                let Ok(CatalogRefresh::Updated(catalog)) =
+                    block_on(refresh_in(&dir, built_in, &sources, true))
+                else {
+                    panic!("{location}: expected its own key to verify it");
+                };
+                assert!(!catalog.warnings.contains(&UNSIGNED_WARNING.to_string()));
+                assert_eq!(cached(&dir), SIGNED);
+
+                // And the cache it left is trusted on the next start
+                let (catalog, _) = block_on(read_trusted_cache(&dir, built_in, &sources)).unwrap();
+                assert_eq!(catalog.packages[0].id, "git");
+                assert!(block_on(read_trusted_cache(&dir, Some(OTHER_PUBLIC_KEY), &[])).is_err());
+                let _ = std::fs::remove_file(dir.join(CACHE_FILE));
+            }
+        }
+    }
+
+    #[test]
+    fn unsigned_build_loads_catalogs_with_a_warning() {
+        let dir = cache_dir("unsigned");
+        let raw = format!("{SIGNED}\n# not what was signed\n");
+        let (url, _) = serve(raw.clone());
+
+        let Ok(CatalogRefresh::Updated(catalog)) =
+            block_on(refresh_in(&dir, None, &[source(&url, None)], false))
+        else {
+            panic!("expected the unsigned catalog");
+        };
+        assert!(catalog.warnings.contains(&UNSIGNED_WARNING.to_string()));
+        assert_eq!(cached(&dir), raw);
+
+        let catalog = block_on(read_cache(&dir, None)).unwrap();
+        assert!(catalog.warnings.contains(&UNSIGNED_WARNING.to_string()));
+        // A build with a key doesn't trust what an unsigned build cached
+        assert!(block_on(read_cache(&dir, Some(TEST_PUBLIC_KEY))).is_err());
+    }
+
+    /// A catalog with one profile and one category, plus `packages`.
+    fn catalog_toml(packages: &str) -> String {
+        let (major, minor) = SCHEMA_VERSION;
+        catalog_with_header(&format!(r#"schema_version = "{major}.{minor}""#), packages)
+    }
+
+    /// `catalog_toml` with its own top-level `header` lines.
+    fn catalog_with_header(header: &str, packages: &str) -> String {
+        format!(
+            r#"{header}
+
+[[categories]]
+slug = "tools"
+name = "Tools"
+
+[[profiles]]
+slug = "all"
+title = "All"
+description = "Everything"
+icon = "monitor"
+
+{packages}"#
+        )
+    }
+
+    const GIT: &str = r#"[[packages]]
+id = "git"
+name = "Git"
+description = "Version control"
+category = "tools"
+winget_id = "Git.Git"
+"#;
+
+    #[test]
+    fn newer_minor_schema_loads_with_a_warning() {
+        let (major, minor) = SCHEMA_VERSION;
+        let header = format!(
+            "schema_version = \"{major}.{}\"\nmirror_name = \"HQ\"",
+            minor + 1
+        );
+        let packages = format!("{GIT}added_in_a_later_minor = true\n");
+        let raw = catalog_with_header(&header, &packages);
+
+        let catalog = parse_catalog_toml(&raw, None).unwrap();
+        assert_eq!(catalog.packages[0].id, "git");
+        assert!(
+            catalog.warnings[0].contains("is newer than this app"),
+            "{:?}",
+            catalog.warnings
+        );
+    }
+
+    #[test]
+    fn other_major_schemas_are_refused() {
+        let major = SCHEMA_VERSION.0;
+        let newer = catalog_with_header(&format!(r#"schema_version = "{}.0""#, major + 1), GIT);
+        let err = parse_catalog_toml(&newer, None).unwrap_err();
+        assert!(err.contains("needs a newer version of Provision"), "{err}");
+
+        let older = catalog_with_header(&format!(r#"schema_version = "{}.9""#, major - 1), GIT);
+        let err = parse_catalog_toml(&older, None).unwrap_err();
+        assert!(
+            err.contains("is older than this version of Provision"),
+            "{err}"
+        );
+
+        // No schema_version at all counts as 1.0
+        let err = check_schema(&catalog_with_header("", GIT)).unwrap_err();
+        assert!(err.contains("catalog schema 1.0 is older"), "{err}");
+    }
+
+    #[test]
+    fn catalog_for_a_newer_app_asks_for_an_update() {
+        let [major, minor, patch] = parse_app_version(env!("CARGO_PKG_VERSION"));
+        let (schema_major, schema_minor) = SCHEMA_VERSION;
+        let header = format!(
+            "schema_version = \"{schema_major}.{schema_minor}\"\nmin_app_version = \"{major}.{minor}.{}\"",
+            patch + 1
+        );
+        let warnings = check_schema(&catalog_with_header(&header, GIT)).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("please update"), "{warnings:?}");
+
+        let header = format!(
+            "schema_version = \"{schema_major}.{schema_minor}\"\nmin_app_version = \"{}\"",
+            env!("CARGO_PKG_VERSION")
+        );
+        assert!(
+            check_schema(&catalog_with_header(&header, GIT))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    /// `UPDATE_SCHEMAS=1` (`just write-schema`) rewrites the files instead.
+    #[test]
+    fn schemas_are_up_to_date() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("schemas");
+        let update = std::env::var_os("UPDATE_SCHEMAS").is_some();
+        for (name, json) in json_schemas() {
+            let path = dir.join(name);
+            if update {
+                std::fs::write(&path, &json).unwrap();
+                continue;
+            }
+            let committed = std::fs::read_to_string(&path)
+                .unwrap_or_default()
+                .replace("\r\n", "\n");
+            assert!(
+                committed == json,
+                "{} is out of date; run `just write-schema`",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn overlay_winget_id_replaces_install_command() {
+        let raw = catalog_toml(
+            r#"[[packages]]
+id = "tool"
+name = "Tool"
+description = "Installed by a script upstream"
+category = "tools"
+install_command = "curl -L https://example.com/install.cmd | cmd"
+"#,
+        );
+        let overlay = r#"[[packages]]
+id = "tool"
+winget_id = "Example.Tool"
+"#;
+
+        let catalog = parse_catalog_toml(&raw, Some(overlay)).unwrap();
+        let tool = &catalog.packages[0];
+        assert_eq!(tool.install_command, None);
+        assert_eq!(
+            tool.installer,
+            Some(Installer::Winget {
+                id: "Example.Tool".into()
+            })
+        );
+    }
+
+    #[test]
+    fn overlay_install_method_drops_the_catalog_winget_id() {
+        let raw = catalog_toml(
+            r#"[[packages]]
+id = "tool"
+name = "Tool"
+description = "From winget upstream"
+category = "tools"
+winget_id = "Example.Tool"
+
+[[packages]]
+id = "other"
+name = "Other"
+description = "From winget upstream"
+category = "tools"
+winget_id = "Example.Other"
+"#,
+        );
+        let overlay = r#"[[packages]]
+id = "tool"
+install_command = "\\\\share\\tools\\tool-setup.exe /S"
+
+[[packages]]
+id = "other"
+installer = { type = "scoop", name = "other" }
+"#;
+
+        let catalog = parse_catalog_toml(&raw, Some(overlay)).unwrap();
+        let [tool, other] = catalog.packages.as_slice() else {
+            panic!("expected two packages");
+        };
+        assert_eq!(tool.winget_id, None);
+        assert!(matches!(tool.installer, Some(Installer::Command { .. })));
+        assert_eq!(other.winget_id, None);
+        assert_eq!(
+            other.installer,
+            Some(Installer::Scoop {
+                name: "other".into(),
+                bucket: None
+            })
+        );
+    }
+
+    #[test]
+    fn unreadable_packages_are_skipped_with_their_dependents() {
+        let raw = catalog_toml(
+            r#"[[packages]]
+id = "git"
+name = "Git"
+description = "Version control"
+category = "tools"
+winget_id = "Git.Git"
+
+[[packages]]
+id = "future"
+name = "Future"
+description = "Uses a field type this build doesn't know"
+category = "tools"
+winget_id = "Future.Future"
+post_install = 42
+
+[[packages]]
+id = "needs-future"
+name = "Needs Future"
+description = "Depends on the unreadable package"
+category = "tools"
+winget_id = "Needs.Future"
+depends_on = ["future"]
+"#,
+        );
+
+        let catalog = parse_catalog_toml(&raw, None).unwrap();
+        let ids: Vec<&str> = catalog.packages.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["git"]);
+        assert_eq!(catalog.warnings.len(), 2);
+        assert!(catalog.warnings[0].starts_with("Skipped package 'future'"));
+        assert!(catalog.warnings[1].contains("depends on skipped package 'future'"));
+    }
+
+    #[test]
+    fn post_install_accepts_a_command_line_or_steps() {
+        let raw = catalog_toml(
+            r#"[[packages]]
+id = "old"
+name = "Old"
+description = "Command line"
+category = "tools"
+winget_id = "Old.Old"
+post_install = "echo done"
+
+[[packages]]
+id = "new"
+name = "New"
+description = "Steps"
+category = "tools"
+winget_id = "New.New"
+post_install = [{ type = "cmd", command = "echo done" }]
+"#,
+        );
+
+        let catalog = parse_catalog_toml(&raw, None).unwrap();
+        assert_eq!(catalog.packages.len(), 2);
+        assert!(catalog.warnings.is_empty());
+    }
+
+    #[test]
+    fn unknown_types_load_as_unsupported() {
+        let raw = catalog_toml(
+            r#"[[packages]]
+id = "future-installer"
+name = "Future Installer"
+description = "Installer type from a newer catalog"
+category = "tools"
+installer = { type = "teleport", target = "C:\\Tools" }
+
+[[packages]]
+id = "future-step"
+name = "Future Step"
+description = "Post-install step type from a newer catalog"
+category = "tools"
+winget_id = "Future.Step"
+detect = { type = "wmi", query = "SELECT 1" }
+post_install = [{ type = "reboot", delay = 5 }]
+"#,
+        );
+
+        let catalog = parse_catalog_toml(&raw, None).unwrap();
+        assert!(catalog.warnings.is_empty());
+        let [installer, step] = catalog.packages.as_slice() else {
+            panic!("expected two packages");
+        };
+        assert_eq!(installer.installer, Some(Installer::Unsupported));
+        assert_eq!(step.detect, Some(Detect::Unsupported));
+        let system = SystemInfo::default();
+        for package in [installer, step] {
+            assert_eq!(
+                package.incompatibility(&system).as_deref(),
+                Some("Needs a newer version of Provision")
+            );
+        }
+    }
+
+    #[test]
+    fn dependencies_are_selected_and_dependents_follow_them_out() {
+        let raw = catalog_toml(
+            r#"[[packages]]
+id = "nodejs"
+name = "Node.js"
+description = "JavaScript runtime"
+category = "tools"
+winget_id = "OpenJS.NodeJS.LTS"
+
+[[packages]]
+id = "typescript"
+name = "TypeScript"
+description = "Typed JavaScript"
+category = "tools"
+install_command = "npm install -g typescript"
+depends_on = ["nodejs"]
+
+[[packages]]
+id = "ts-node"
+name = "ts-node"
+description = "TypeScript REPL"
+category = "tools"
+install_command = "npm install -g ts-node"
+depends_on = ["typescript"]
+"#,
+        );
+        let catalog = parse_catalog_toml(&raw, None).unwrap();
+        let ids = |packages: Vec<&Package>| -> Vec<String> {
+            packages.iter().map(|p| p.id.clone()).collect()
+        };
+
+        let selected = HashSet::from(["ts-node".to_string()]);
+        assert_eq!(
+            ids(missing_dependencies(&catalog.packages, &selected)),
+            ["nodejs", "typescript"]
+        );
+        assert_eq!(
+            ids(dependents(&catalog.packages, "nodejs")),
+            ["typescript", "ts-node"]
+        );
+        assert!(dependents(&catalog.packages, "ts-node").is_empty());
+    }
+}
@@ -0,0 +1,257 @@
+//! Merge a split catalog directory back into a single packages.toml.
+//!
+//! ```text
+//! packages/
+//!   catalog.toml              schema_version, [[categories]], [[profiles]]
+//!   browsers/firefox.toml     one package: its fields, without [[packages]]
+//!   development/git.toml
+//! ```
+//!
+//! The merged text is laid out exactly like `sort_packages` output, so a
+//! signature made over `split_packages --merge` matches what the app merges
+//! from the same directory. Shared by build.rs, the app, and the catalog
+//! tools through `#[path]`, so it only depends on `toml` and
+//! `unicode-normalization`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// File in the catalog directory holding everything but the packages.
+pub const HEADER_FILE: &str = "catalog.toml";
+
+/// Start of a section banner comment, e.g. `# ── Browsers ───…`.
+pub const BANNER_PREFIX: &str = "# ──";
+
+/// A merged catalog and where each of its lines came from.
+pub struct Merged {
+    pub text: String,
+    /// `(first merged line, file, matching line in that file)`, in order.
+    parts: Vec<(usize, PathBuf, usize)>,
+}
+
+impl Merged {
+    /// The file and 1-based line a merged line came from. Banners and the
+    /// `[[packages]]` headers added by the merge map to the package's first
+    /// field.
+    pub fn locate(&self, line: usize) -> (&Path, usize) {
+        let Some((start, path, file_line)) = self.parts.iter().rev().find(|(s, ..)| *s <= line)
+        else {
+            return (Path::new(HEADER_FILE), line);
+        };
+        (path, file_line + (line - start))
+    }
+}
+
+/// A package file, read and checked.
+struct PackageFile {
+    path: PathBuf,
+    sort_key: (String, String, String),
+    /// Comment lines above the package, moved above its `[[packages]]`.
+    comments: String,
+    body: String,
+    /// Line of `body`'s first line in the file.
+    body_line: usize,
+}
+
+/// Section banner comment, e.g. `# ── Browsers ───…`.
+pub fn banner(title: &str) -> String {
+    let bar = "─".repeat(57 - title.len().min(55));
+    format!("{BANNER_PREFIX} {title} {bar}\n")
+}
+
+/// Sort key for a package name: lowercase with accents stripped, so
+/// "Ähnlich" sorts with "ahnlich" and "éclair" before "Firefox".
+pub fn sort_key(name: &str) -> String {
+    name.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// 1-based line number of a byte offset in `raw`.
+fn line_of(raw: &str, offset: usize) -> usize {
+    raw[..offset.min(raw.len())].matches('\n').count() + 1
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn parse(path: &Path, raw: &str) -> Result<toml::Table, String> {
+    raw.parse::<toml::Table>().map_err(|e| {
+        let line = e.span().map(|s| line_of(raw, s.start)).unwrap_or(0);
+        format!("{}:{line}: {}", path.display(), e.message())
+    })
+}
+
+/// Sorted directory entries, so merges don't depend on the file system.
+fn entries(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .and_then(|entries| entries.map(|e| e.map(|e| e.path())).collect())
+        .map_err(|e| format!("{}: {e}", dir.display()))?;
+    paths.sort();
+    Ok(paths)
+}
+
+/// Whether `dir` is a split catalog, i.e. has a `catalog.toml`.
+pub fn is_catalog_dir(dir: &Path) -> bool {
+    dir.join(HEADER_FILE).is_file()
+}
+
+/// Read one package file, checking that it holds a single package whose
+/// `id` and `category` match its path.
+fn read_package(path: &Path, category: &str) -> Result<PackageFile, String> {
+    let raw = read(path)?;
+    let table = parse(path, &raw)?;
+    let field = |key| table.get(key).and_then(toml::Value::as_str);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+
+    match field("id") {
+        Some(id) if id == stem => {}
+        Some(id) => {
+            return Err(format!(
+                "{}: id '{id}' doesn't match the file name",
+                path.display()
+            ));
+        }
+        None => return Err(format!("{}: missing id", path.display())),
+    }
+    match field("category") {
+        Some(c) if c == category => {}
+        Some(c) => {
+            return Err(format!(
+                "{}: category '{c}' doesn't match its folder '{category}'",
+                path.display()
+            ));
+        }
+        None => return Err(format!("{}: missing category", path.display())),
+    }
+
+    // Leading comments go above the [[packages]] header; banners are redrawn
+    let lines: Vec<&str> = raw.lines().collect();
+    let body_start = lines
+        .iter()
+        .position(|line| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .unwrap_or(lines.len());
+    let comments: String = lines[..body_start]
+        .iter()
+        .map(|line| line.trim_end())
+        .filter(|line| !line.is_empty() && !line.starts_with(BANNER_PREFIX))
+        .map(|line| format!("{line}\n"))
+        .collect();
+    let body = format!("{}\n", lines[body_start..].join("\n").trim_end());
+
+    // `[installer]`-style tables would attach to the catalog root once the
+    // body follows a [[packages]] header
+    let merged = parse(path, &format!("[[packages]]\n{body}"))?;
+    if merged.len() != 1 {
+        return Err(format!(
+            "{}: tables must be inline, e.g. `installer = {{ ... }}`",
+            path.display()
+        ));
+    }
+
+    let name = field("name").unwrap_or_default();
+    Ok(PackageFile {
+        path: path.to_path_buf(),
+        sort_key: (sort_key(name), name.to_string(), stem.to_string()),
+        comments,
+        body,
+        body_line: body_start + 1,
+    })
+}
+
+/// Merge a split catalog directory into one catalog, with packages grouped
+/// by category in declared order and sorted by name.
+pub fn merge_dir(dir: &Path) -> Result<Merged, String> {
+    let header_path = dir.join(HEADER_FILE);
+    let header_raw = read(&header_path)?;
+    let header = parse(&header_path, &header_raw)?;
+    if header.contains_key("packages") {
+        return Err(format!(
+            "{}: packages go in <category>/<id>.toml, not here",
+            header_path.display()
+        ));
+    }
+
+    // Declared category order and display names; ties keep declaration order
+    let mut categories: Vec<(i64, &str, &str)> = header
+        .get("categories")
+        .and_then(toml::Value::as_array)
+        .map(|cats| {
+            cats.iter()
+                .map(|c| {
+                    let field = |key| c.get(key).and_then(toml::Value::as_str);
+                    let order = c.get("order").and_then(toml::Value::as_integer);
+                    (
+                        order.unwrap_or(0),
+                        field("slug").unwrap_or_default(),
+                        field("name").unwrap_or_default(),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    categories.sort_by_key(|(order, ..)| *order);
+
+    let mut groups: Vec<(&str, Vec<PackageFile>)> = categories
+        .iter()
+        .map(|(_, slug, _)| (*slug, Vec::new()))
+        .collect();
+    for path in entries(dir)? {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        if !path.is_dir() {
+            if path.extension().is_some_and(|e| e == "toml") && name != HEADER_FILE {
+                return Err(format!(
+                    "{}: packages go in a category folder",
+                    path.display()
+                ));
+            }
+            continue;
+        }
+        let Some((_, packages)) = groups.iter_mut().find(|(slug, _)| *slug == name) else {
+            return Err(format!(
+                "{}: '{name}' is not a declared category",
+                path.display()
+            ));
+        };
+        for file in entries(&path)? {
+            if file.extension().is_some_and(|e| e == "toml") {
+                packages.push(read_package(&file, name)?);
+            }
+        }
+    }
+
+    let mut text = format!("{}\n", header_raw.trim_end());
+    let mut parts = vec![(1, header_path, 1)];
+    for ((_, _, title), (_, mut packages)) in categories.iter().zip(groups) {
+        if packages.is_empty() {
+            continue;
+        }
+        packages.sort_by(|a, b| a.sort_key.cmp(&b.sort_key));
+        text.push_str("\n\n");
+        text.push_str(&banner(title));
+        for package in packages {
+            text.push('\n');
+            text.push_str(&package.comments);
+            text.push_str("[[packages]]\n");
+            let header_line = line_of(&text, text.len()) - 1;
+            parts.push((header_line, package.path.clone(), package.body_line));
+            parts.push((header_line + 1, package.path, package.body_line));
+            text.push_str(&package.body);
+        }
+    }
+    Ok(Merged { text, parts })
+}
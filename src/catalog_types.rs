@@ -0,0 +1,449 @@
+//! Types `packages.toml` deserializes into: packages, categories, and the
+//! installer, detect and post-install tables inside a package.
+//!
+//! Only the data and what can be worked out from it live here; installing,
+//! detecting and running steps are in their own modules. Shared by the app
+//! and `lint_packages` through `#[path]`, so it only depends on `serde` and
+//! `schemars`.
+
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct Package {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub category: String,
+    /// Project website, linked from the details panel.
+    pub homepage: Option<String>,
+    /// License name or SPDX expression, e.g. `"MIT"` or `"Freeware"`.
+    pub license: Option<String>,
+    pub publisher: Option<String>,
+    /// Lucide icon name, e.g. `"terminal"`.
+    pub icon: Option<String>,
+    /// Anything worth knowing before picking the package, e.g. "Free for
+    /// personal use only".
+    pub notes: Option<String>,
+    pub winget_id: Option<String>,
+    /// Version to install instead of the latest; upgrades past it are flagged.
+    pub version: Option<VersionPin>,
+    #[serde(default)]
+    pub profiles: Vec<String>,
+    /// A `cmd /C` line, or a list of typed steps run after the install.
+    pub post_install: Option<PostInstall>,
+    pub install_command: Option<String>,
+    /// Command line for `cmd /C` that removes the package. Defaults to the
+    /// installer's own uninstall, e.g. `winget uninstall --id ... -e`.
+    pub uninstall_command: Option<String>,
+    /// How to install the package. Filled in from `winget_id` or
+    /// `install_command` when not given explicitly.
+    pub installer: Option<Installer>,
+    /// Package IDs that must be installed before this one.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Free-form facets for filtering, e.g. `"cli"` or `"open-source"`.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Per-package winget options, merged over the global settings.
+    #[serde(default)]
+    pub winget: WingetOverrides,
+    /// Architectures the package runs on; empty means all.
+    #[serde(default)]
+    pub arch: Vec<Architecture>,
+    /// Lowest Windows build the package supports, e.g. `22000` for Windows 11.
+    pub min_os_build: Option<u32>,
+    /// Highest Windows build the package supports.
+    pub max_os_build: Option<u32>,
+    /// How to tell the package is installed when its installer can't list
+    /// it (commands and downloads).
+    pub detect: Option<Detect>,
+    /// Precomputed key into the installed-package map (see
+    /// `Installer::installed_key`), or `detect:<id>` for detect rules.
+    #[serde(skip)]
+    pub installed_key: Option<String>,
+}
+
+/// A pinned package version: exact (`"1.2.3"`) or a prefix ending in `.*`
+/// (`"1.2.*"`) that accepts any release in that line.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(try_from = "String")]
+pub struct VersionPin(String);
+
+impl TryFrom<String> for VersionPin {
+    type Error = String;
+
+    fn try_from(pin: String) -> Result<Self, String> {
+        let prefix = pin.strip_suffix(".*").unwrap_or(&pin);
+        let valid = !prefix.is_empty()
+            && prefix
+                .split('.')
+                .all(|s| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '-'));
+        if valid {
+            Ok(Self(pin))
+        } else {
+            Err(format!("invalid version pin '{pin}'"))
+        }
+    }
+}
+
+impl VersionPin {
+    /// The version to pass to winget as-is, or `None` for a `.*` prefix.
+    pub fn exact(&self) -> Option<&str> {
+        (!self.0.ends_with(".*")).then_some(self.0.as_str())
+    }
+
+    /// True if `version` satisfies this pin.
+    pub fn matches(&self, version: &str) -> bool {
+        match self.0.strip_suffix(".*") {
+            Some(prefix) => {
+                version == prefix
+                    || version
+                        .strip_prefix(prefix)
+                        .is_some_and(|rest| rest.starts_with('.'))
+            }
+            None => compare_versions(version, &self.0).is_eq(),
+        }
+    }
+}
+
+impl std::fmt::Display for VersionPin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Compare dotted versions segment by segment, numerically where possible,
+/// treating missing segments as zero (`1.2` == `1.2.0`).
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let mut a = a.split('.');
+    let mut b = b.split('.');
+    loop {
+        let (x, y) = match (a.next(), b.next()) {
+            (None, None) => return std::cmp::Ordering::Equal,
+            (x, y) => (x.unwrap_or("0"), y.unwrap_or("0")),
+        };
+        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.cmp(y),
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+/// Winget options for a single package, written as an inline table:
+/// `winget = { scope = "machine", custom = "/NORESTART" }`.
+///
+/// `scope`, `architecture` and `locale` replace the global settings,
+/// `override` replaces the installer's switches entirely (so the global
+/// silent/interactive mode no longer applies), and `custom` and `extra_args`
+/// are passed in addition to the global flags.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct WingetOverrides {
+    pub scope: Option<InstallScope>,
+    pub architecture: Option<Architecture>,
+    /// Installer switches passed with `--override`.
+    #[serde(rename = "override")]
+    pub override_args: Option<String>,
+    /// Extra installer switches passed with `--custom`.
+    pub custom: Option<String>,
+    pub locale: Option<String>,
+    /// Additional raw winget arguments.
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+}
+
+impl WingetOverrides {
+    /// One-line description for the review screen, or `None` if nothing is set.
+    pub fn summary(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(scope) = self.scope {
+            parts.push(format!("scope {}", scope.to_string().to_lowercase()));
+        }
+        if let Some(arch) = self.architecture {
+            parts.push(format!("{arch}"));
+        }
+        if let Some(ref locale) = self.locale {
+            parts.push(format!("locale {locale}"));
+        }
+        if let Some(ref args) = self.override_args {
+            parts.push(format!("override \"{args}\""));
+        }
+        if let Some(ref args) = self.custom {
+            parts.push(format!("custom \"{args}\""));
+        }
+        if !self.extra_args.is_empty() {
+            parts.push(self.extra_args.join(" "));
+        }
+        (!parts.is_empty()).then(|| parts.join(" \u{00b7} "))
+    }
+}
+
+/// A package category, declared in the catalog's `[[categories]]` table.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct Category {
+    pub slug: String,
+    pub name: String,
+    /// Lucide icon name, e.g. `"globe"`.
+    pub icon: Option<String>,
+    /// Sort key; lower values are listed first, ties keep declaration order.
+    #[serde(default)]
+    pub order: i32,
+}
+
+// ── Install scope and architecture ───────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallScope {
+    User,
+    Machine,
+}
+
+impl fmt::Display for InstallScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::User => write!(f, "User"),
+            Self::Machine => write!(f, "Machine"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Architecture {
+    #[serde(rename = "x86")]
+    X86,
+    #[serde(rename = "x64")]
+    X64,
+    #[serde(rename = "arm64")]
+    Arm64,
+}
+
+impl fmt::Display for Architecture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::X86 => write!(f, "x86"),
+            Self::X64 => write!(f, "x64"),
+            Self::Arm64 => write!(f, "arm64"),
+        }
+    }
+}
+
+// ── Installers ───────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Installer {
+    Winget {
+        id: String,
+    },
+    /// Microsoft Store product, installed through winget's `msstore` source.
+    Msstore {
+        id: String,
+    },
+    Scoop {
+        name: String,
+        /// Bucket to add before installing, e.g. `"extras"`.
+        bucket: Option<String>,
+    },
+    Choco {
+        name: String,
+    },
+    /// A crate installed with `cargo install`.
+    Cargo {
+        name: String,
+    },
+    /// A global npm package.
+    Npm {
+        name: String,
+    },
+    Pipx {
+        name: String,
+    },
+    /// A module from the PowerShell Gallery.
+    PowershellModule {
+        name: String,
+    },
+    /// An arbitrary command line, run through `cmd /C`.
+    Command {
+        command: String,
+    },
+    /// An installer file fetched and checksummed by Provision itself.
+    Download(Download),
+    /// A type this version doesn't know, from a newer catalog. The package
+    /// is listed but can't be installed.
+    #[serde(other)]
+    #[schemars(skip)]
+    Unsupported,
+}
+
+/// `installer = { type = "download", url = "...", sha256 = "..." }`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+pub struct Download {
+    pub url: String,
+    /// Expected SHA-256 of the file, as hex.
+    pub sha256: String,
+    /// Arguments for an unattended install. For msi files they replace the
+    /// default `/qn /norestart`; for zip files they go to `run`.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Guessed from the URL's extension when omitted.
+    pub file_type: Option<FileType>,
+    /// Zip only: file inside the archive to run after extracting.
+    pub run: Option<String>,
+    /// Zip only: folder to extract into (environment variables are expanded).
+    /// Defaults to a temporary folder.
+    pub extract_to: Option<String>,
+}
+
+impl Download {
+    /// Last path segment of the URL, without the query string.
+    pub fn file_name(&self) -> &str {
+        let path = self.url.split(['?', '#']).next().unwrap_or_default();
+        path.rsplit('/')
+            .next()
+            .filter(|name| !name.is_empty())
+            .unwrap_or("download")
+    }
+
+    /// Why `file_name` can't be saved as is: it must stay inside the
+    /// download folder and can't name an NTFS stream or a drive.
+    pub fn file_name_problem(&self) -> Option<String> {
+        let name = self.file_name();
+        (name.contains(['\\', ':']) || name.contains(".."))
+            .then(|| format!("download file name '{name}' contains '\\', ':' or '..'"))
+    }
+
+    pub fn file_type(&self) -> Option<FileType> {
+        self.file_type.or_else(|| {
+            let name = self.file_name().to_ascii_lowercase();
+            let (_, ext) = name.rsplit_once('.')?;
+            match ext {
+                "exe" => Some(FileType::Exe),
+                "msi" => Some(FileType::Msi),
+                "zip" => Some(FileType::Zip),
+                _ => None,
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FileType {
+    Exe,
+    Msi,
+    Zip,
+}
+
+// ── Detect rules ─────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Detect {
+    /// A file exists.
+    File { path: String },
+    /// A folder exists.
+    Dir { path: String },
+    /// A program runs and exits successfully. With `version_regex`, its
+    /// output must also match; the first capture group (or the whole match)
+    /// is the version.
+    Command {
+        command: String,
+        #[serde(default = "default_command_args")]
+        args: Vec<String>,
+        version_regex: Option<String>,
+    },
+    /// A registry key exists, e.g.
+    /// `HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\Foo`. With
+    /// `value`, that value must exist too and its data is the version.
+    Registry { key: String, value: Option<String> },
+    /// A type this version doesn't know, from a newer catalog. It never
+    /// holds.
+    #[serde(other)]
+    #[schemars(skip)]
+    Unsupported,
+}
+
+fn default_command_args() -> Vec<String> {
+    vec!["--version".into()]
+}
+
+// ── Post-install steps ───────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum PostInstall {
+    /// One command line for `cmd /C`, as in older catalogs. A failure is
+    /// only a warning.
+    Command(String),
+    Steps(Vec<Step>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+pub struct Step {
+    #[serde(flatten)]
+    pub action: Action,
+    /// Label for the progress screen; defaults to a description of the action.
+    pub label: Option<String>,
+    #[serde(default)]
+    pub run_as: RunAs,
+    /// Log a failure as a warning and go on with the next step, instead of
+    /// failing the package.
+    #[serde(default)]
+    pub continue_on_error: bool,
+    /// Seconds before the step is stopped and counted as failed.
+    pub timeout: Option<u64>,
+    /// Exit codes that mean success; `[0]` when empty. Add `3010` for
+    /// commands that report "reboot required".
+    #[serde(default)]
+    pub success_codes: Vec<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Action {
+    /// A command line, run through `cmd /C`.
+    Cmd { command: String },
+    /// A PowerShell script, run with `-ExecutionPolicy Bypass`.
+    Powershell { script: String },
+    /// A persistent environment variable, set with `setx`. Environment
+    /// variables in `value` are expanded first.
+    SetEnv {
+        name: String,
+        value: String,
+        #[serde(default)]
+        scope: EnvScope,
+    },
+    /// Copy a file, creating the destination folder. Environment variables
+    /// in both paths are expanded.
+    CopyFile { from: String, to: String },
+    /// A type this version doesn't know, from a newer catalog. A package
+    /// with such a step can't be installed.
+    #[serde(other)]
+    #[schemars(skip)]
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RunAs {
+    /// With Provision's own administrator rights.
+    #[default]
+    Elevated,
+    /// De-elevated, as the signed-in user, for per-user settings. The step
+    /// runs in its own window and its output isn't captured.
+    User,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvScope {
+    #[default]
+    User,
+    Machine,
+}
@@ -0,0 +1,121 @@
+//! Live package details from `winget show`, shown in the package picker's
+//! details panel next to the catalog's own metadata.
+
+use tokio::process::Command;
+
+use crate::installer::Installer;
+use crate::upgrade;
+
+/// What `winget show` reports about a package. Fields it didn't list are
+/// empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WingetDetails {
+    /// Latest version in the source.
+    pub version: String,
+    pub publisher: String,
+    pub license: String,
+    pub homepage: String,
+    /// e.g. `msi`, `inno`, `msix`.
+    pub installer_type: String,
+    pub release_notes_url: String,
+}
+
+/// `winget show` arguments for winget and Store packages; `None` for other
+/// backends, which winget doesn't know about.
+pub fn show_args(installer: &Installer) -> Option<Vec<String>> {
+    let (id, source) = match installer {
+        Installer::Winget { id } => (id, None),
+        Installer::Msstore { id } => (id, Some("msstore")),
+        _ => return None,
+    };
+    let mut args = vec!["show".into(), "--id".into(), id.clone(), "-e".into()];
+    if let Some(source) = source {
+        args.push("--source".into());
+        args.push(source.into());
+    }
+    args.push("--accept-source-agreements".into());
+    Some(args)
+}
+
+/// Run `winget show` and parse its output.
+pub async fn fetch(args: Vec<String>, dry_run: bool) -> Result<WingetDetails, String> {
+    if dry_run {
+        tokio::time::sleep(std::time::Duration::from_millis(400)).await;
+        return Ok(WingetDetails {
+            version: "1.0.0".into(),
+            publisher: "Example Publisher".into(),
+            license: "MIT".into(),
+            homepage: "https://example.com".into(),
+            installer_type: "msi".into(),
+            release_notes_url: "https://example.com/releases".into(),
+        });
+    }
+
+    let output = Command::new("winget")
+        .args(&args)
+        .stderr(std::process::Stdio::null())
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .await
+        .map_err(|e| format!("Failed to spawn winget: {e}"))?;
+
+    parse_show_output(&upgrade::output_lines(&output.stdout))
+        .ok_or_else(|| "winget found no package with this ID".into())
+}
+
+/// Parse `winget show` output:
+///
+/// ```text
+/// Found Git [Git.Git]
+/// Version: 2.47.1
+/// Publisher: The Git Development Community
+/// License: GPL-2.0
+/// Installer:
+///   Installer Type: inno
+/// ```
+///
+/// Top-level fields are unindented; indented lines belong to the section
+/// above them (`Installer:`, or multi-line values like `Release Notes:`).
+/// `None` if there is no `Version` line.
+pub fn parse_show_output(lines: &[String]) -> Option<WingetDetails> {
+    let mut details = WingetDetails::default();
+    let mut section = "";
+
+    for line in lines {
+        let indented = line.starts_with(' ');
+        let Some((key, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+
+        if !indented {
+            section = if value.is_empty() { key } else { "" };
+        }
+        let field = match (indented, section, key) {
+            (false, _, "Version") => &mut details.version,
+            (false, _, "Publisher") => &mut details.publisher,
+            (false, _, "License") => &mut details.license,
+            (false, _, "Homepage") => &mut details.homepage,
+            (false, _, "Release Notes Url") => &mut details.release_notes_url,
+            (true, "Installer", "Installer Type") => &mut details.installer_type,
+            _ => continue,
+        };
+        *field = value;
+    }
+
+    (!details.version.is_empty()).then_some(details)
+}
+
+/// Open a web page in the default browser. Anything but http(s) is ignored,
+/// since catalog URLs come from the network.
+pub async fn open_url(url: String) {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return;
+    }
+    // Unlike `start`, this takes the URL as-is, `&` and all
+    let _ = Command::new("rundll32")
+        .args(["url.dll,FileProtocolHandler", &url])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .status()
+        .await;
+}
@@ -0,0 +1,95 @@
+//! Detection rules for packages their installer can't list, such as
+//! install scripts and downloads:
+//!
+//! ```toml
+//! detect = { type = "command", command = "%USERPROFILE%\\.local\\bin\\uv.exe", version_regex = "uv (\\S+)" }
+//! ```
+//!
+//! Paths and commands have `%VARIABLES%` expanded. A rule that holds marks
+//! the package installed, with a version where the rule can read one.
+
+use std::path::Path;
+
+use regex::Regex;
+use tokio::process::Command;
+
+pub use crate::catalog_types::Detect;
+use crate::system;
+
+impl Detect {
+    /// The installed version if the rule holds (empty when unknown), or
+    /// `None` if the package isn't installed.
+    pub async fn check(&self) -> Option<String> {
+        match self {
+            Self::File { path } => {
+                let path = system::expand_env_vars(path);
+                Path::new(&path).is_file().then(String::new)
+            }
+            Self::Dir { path } => {
+                let path = system::expand_env_vars(path);
+                Path::new(&path).is_dir().then(String::new)
+            }
+            Self::Command {
+                command,
+                args,
+                version_regex,
+            } => {
+                let output = run(&system::expand_env_vars(command), args).await?;
+                match version_regex {
+                    Some(pattern) => capture_version(pattern, &output),
+                    None => Some(String::new()),
+                }
+            }
+            Self::Registry { key, value } => {
+                let mut args = vec!["query".to_string(), key.clone()];
+                if let Some(value) = value {
+                    args.push("/v".into());
+                    args.push(value.clone());
+                }
+                let output = run("reg", &args).await?;
+                match value {
+                    Some(value) => parse_reg_value(&output, value),
+                    None => Some(String::new()),
+                }
+            }
+            Self::Unsupported => None,
+        }
+    }
+}
+
+/// Stdout of a successful run, or `None` if the program is missing or fails.
+async fn run(program: &str, args: &[String]) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .stderr(std::process::Stdio::null())
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .await
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// First capture group of `pattern` in `output`, or the whole match.
+/// An invalid pattern never matches (the linter reports it).
+fn capture_version(pattern: &str, output: &str) -> Option<String> {
+    let captures = Regex::new(pattern).ok()?.captures(output)?;
+    let found = captures.get(1).or_else(|| captures.get(0))?;
+    Some(found.as_str().trim().to_string())
+}
+
+/// Data of `value` in `reg query` output, whose lines look like
+/// `    DisplayVersion    REG_SZ    1.2.3`.
+fn parse_reg_value(output: &str, value: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let mut parts = line.trim().splitn(3, "    ");
+        let name = parts.next()?;
+        let kind = parts.next()?;
+        if !name.eq_ignore_ascii_case(value) || !kind.starts_with("REG_") {
+            return None;
+        }
+        Some(parts.next().unwrap_or_default().trim().to_string())
+    })
+}
@@ -0,0 +1,459 @@
+//! The `download` installer: fetch an installer file, verify its SHA-256,
+//! then run it (exe), hand it to msiexec (msi), or extract it (zip).
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use iced::futures::SinkExt as _;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+
+pub use crate::catalog_types::{Download, FileType};
+use crate::install::{self, InstallProgress, Sender};
+use crate::system;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Minimum time between progress updates.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Arguments passed to msiexec when a package doesn't give its own.
+const MSI_DEFAULT_ARGS: [&str; 2] = ["/qn", "/norestart"];
+
+impl Download {
+    /// What a dry run would do.
+    pub fn describe(&self) -> String {
+        let file = self.file_name();
+        let args = self.args.join(" ");
+        let then = match self.file_type() {
+            Some(FileType::Exe) => format!("run {file} {args}"),
+            Some(FileType::Msi) => format!("msiexec /i {file} {}", self.msi_args().join(" ")),
+            Some(FileType::Zip) => {
+                let dest = self.extract_to.as_deref().unwrap_or("a temporary folder");
+                match self.run {
+                    Some(ref run) => format!("extract to {dest}, run {run} {args}"),
+                    None => format!("extract to {dest}"),
+                }
+            }
+            None => "fail: unknown file type".into(),
+        };
+        format!("download {}, verify SHA-256, {}", self.url, then.trim_end())
+    }
+
+    fn msi_args(&self) -> Vec<String> {
+        if self.args.is_empty() {
+            MSI_DEFAULT_ARGS.iter().map(|a| a.to_string()).collect()
+        } else {
+            self.args.clone()
+        }
+    }
+}
+
+/// Download, verify and install. Progress goes out as `Activity` lines.
+pub async fn install(download: &Download, index: usize, sender: &mut Sender) -> Result<(), String> {
+    let file_type = download
+        .file_type()
+        .ok_or_else(|| format!("Unknown file type for {}", download.file_name()))?;
+
+    if let Some(problem) = download.file_name_problem() {
+        return Err(problem);
+    }
+
+    // The file runs elevated after the checksum, so it must not be
+    // replaceable in between: a user-writable folder such as %TEMP% won't do
+    let dir = create_private_dir()?;
+    let result = fetch_and_install(download, file_type, &dir, index, sender).await;
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+    result
+}
+
+async fn fetch_and_install(
+    download: &Download,
+    file_type: FileType,
+    dir: &Path,
+    index: usize,
+    sender: &mut Sender,
+) -> Result<(), String> {
+    let path = dir.join(download.file_name());
+
+    log(sender, index, format!("Downloading {}", download.url)).await;
+    let digest = fetch(&download.url, &path, index, sender).await?;
+    if !digest.eq_ignore_ascii_case(download.sha256.trim()) {
+        return Err(format!(
+            "Checksum mismatch: expected {}, got {digest}",
+            download.sha256.trim()
+        ));
+    }
+    log(sender, index, "Checksum verified".into()).await;
+
+    match file_type {
+        FileType::Exe => {
+            let program = path.to_string_lossy();
+            install::run_command(&program, &download.args, index, sender).await
+        }
+        FileType::Msi => {
+            let mut args = vec!["/i".to_string(), path.to_string_lossy().into_owned()];
+            args.extend(download.msi_args());
+            install::run_command("msiexec", &args, index, sender).await
+        }
+        FileType::Zip => extract_and_run(download, &path, index, sender).await,
+    }
+}
+
+/// A new folder for one download, named so it can't be guessed in advance.
+/// Creating it fails rather than reuse a folder someone else made.
+fn private_dir_path(base: &Path) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    base.join(format!("provision-download-{}-{nanos}", std::process::id()))
+}
+
+/// Create the download folder in %ProgramData%, where users can add folders
+/// but not rename or delete other people's, with an ACL that only lets
+/// SYSTEM and Administrators in.
+#[cfg(windows)]
+fn create_private_dir() -> Result<PathBuf, String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::LocalFree;
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+    };
+    use windows_sys::Win32::Security::SECURITY_ATTRIBUTES;
+    use windows_sys::Win32::Storage::FileSystem::CreateDirectoryW;
+
+    let base = std::env::var_os("ProgramData").unwrap_or_else(|| r"C:\ProgramData".into());
+    let dir = private_dir_path(Path::new(&base));
+    let wide: Vec<u16> = dir
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    // Protected (not inherited) DACL: full control for SYSTEM and
+    // Administrators, passed on to the files inside
+    let sddl: Vec<u16> = "D:P(A;OICI;FA;;;SY)(A;OICI;FA;;;BA)\0"
+        .encode_utf16()
+        .collect();
+
+    let error = |e| format!("Create {}: {e}", dir.display());
+    unsafe {
+        let mut descriptor = std::ptr::null_mut();
+        let ok = ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            sddl.as_ptr(),
+            SDDL_REVISION_1,
+            &mut descriptor,
+            std::ptr::null_mut(),
+        );
+        if ok == 0 {
+            return Err(error(std::io::Error::last_os_error()));
+        }
+        let attributes = SECURITY_ATTRIBUTES {
+            nLength: size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: descriptor,
+            bInheritHandle: 0,
+        };
+        let ok = CreateDirectoryW(wide.as_ptr(), &attributes);
+        let result = std::io::Error::last_os_error();
+        LocalFree(descriptor);
+        if ok == 0 {
+            return Err(error(result));
+        }
+    }
+    Ok(dir)
+}
+
+#[cfg(not(windows))]
+fn create_private_dir() -> Result<PathBuf, String> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    let dir = private_dir_path(&std::env::temp_dir());
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&dir)
+        .map_err(|e| format!("Create {}: {e}", dir.display()))?;
+    Ok(dir)
+}
+
+/// Stream `url` into `path`, returning the file's SHA-256 as lowercase hex.
+async fn fetch(
+    url: &str,
+    path: &Path,
+    index: usize,
+    sender: &mut Sender,
+) -> Result<String, String> {
+    let client = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| format!("http client: {e}"))?;
+    let mut resp = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Download failed: {e}"))?;
+
+    let total = resp.content_length();
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| format!("Create {}: {e}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut received: u64 = 0;
+    let mut last_report = Instant::now();
+
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| format!("Download failed: {e}"))?
+    {
+        hasher.update(&chunk);
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Write {}: {e}", path.display()))?;
+        received += chunk.len() as u64;
+
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            last_report = Instant::now();
+            let line = progress_line(received, total);
+            let _ = sender.send(InstallProgress::Activity { index, line }).await;
+        }
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("Write {}: {e}", path.display()))?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// e.g. `Downloading 12.4 MB / 48.2 MB (25%)`
+fn progress_line(received: u64, total: Option<u64>) -> String {
+    let mb = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+    match total {
+        Some(total) if total > 0 => format!(
+            "Downloading {:.1} MB / {:.1} MB ({}%)",
+            mb(received),
+            mb(total),
+            received * 100 / total
+        ),
+        _ => format!("Downloading {:.1} MB", mb(received)),
+    }
+}
+
+/// Extract a zip with the `tar` that ships with Windows, then run `run`.
+async fn extract_and_run(
+    download: &Download,
+    archive: &Path,
+    index: usize,
+    sender: &mut Sender,
+) -> Result<(), String> {
+    let dest = match download.extract_to {
+        Some(ref dest) => PathBuf::from(system::expand_env_vars(dest)),
+        None => archive.with_extension(""),
+    };
+    tokio::fs::create_dir_all(&dest)
+        .await
+        .map_err(|e| format!("Create {}: {e}", dest.display()))?;
+
+    log(sender, index, format!("Extracting to {}", dest.display())).await;
+    let args = [
+        "-xf".to_string(),
+        archive.to_string_lossy().into_owned(),
+        "-C".to_string(),
+        dest.to_string_lossy().into_owned(),
+    ];
+    install::run_command("tar", &args, index, sender).await?;
+
+    match download.run {
+        Some(ref run) => {
+            let program = dest.join(run);
+            install::run_command(&program.to_string_lossy(), &download.args, index, sender).await
+        }
+        None => Ok(()),
+    }
+}
+
+async fn log(sender: &mut Sender, index: usize, line: String) {
+    let _ = sender.send(InstallProgress::Log { index, line }).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    use iced::futures::StreamExt as _;
+
+    use super::*;
+
+    /// Not a real program: running it fails, which shows it was run.
+    const BODY: &[u8] = b"MZ this is not really an installer";
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// An empty download folder for one test.
+    fn download_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "provision-download-test-{}-{name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Answer every request for `/app.exe` with `status` and `BODY`, sending
+    /// the second half after a pause so progress gets reported. Returns the URL.
+    fn serve(status: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/app.exe", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                    line.clear();
+                }
+                let header = format!(
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    BODY.len()
+                );
+                let (first, second) = BODY.split_at(BODY.len() / 2);
+                let _ = stream.write_all(header.as_bytes());
+                let _ = stream.write_all(first);
+                let _ = stream.flush();
+                std::thread::sleep(PROGRESS_INTERVAL * 2);
+                let _ = stream.write_all(second);
+            }
+        });
+        url
+    }
+
+    /// Run `fetch_and_install` for an exe served with `status`, expecting
+    /// `sha256`. Returns the result and the lines it sent.
+    fn install_exe(
+        name: &str,
+        status: &'static str,
+        sha256: &str,
+    ) -> (Result<(), String>, Vec<String>) {
+        let download = Download {
+            sha256: sha256.into(),
+            ..download(&serve(status))
+        };
+        let dir = download_dir(name);
+        let (mut sender, receiver) = iced::futures::channel::mpsc::channel(100);
+        let result = block_on(fetch_and_install(
+            &download,
+            FileType::Exe,
+            &dir,
+            0,
+            &mut sender,
+        ));
+        drop(sender);
+        (result, block_on(receiver.map(|p| line(&p)).collect()))
+    }
+
+    /// The text of a `Log` or `Activity` message, marked with its kind.
+    fn line(progress: &InstallProgress) -> String {
+        match progress {
+            InstallProgress::Log { line, .. } => format!("log: {line}"),
+            InstallProgress::Activity { line, .. } => format!("activity: {line}"),
+            _ => "other".into(),
+        }
+    }
+
+    fn sha256(bytes: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(bytes))
+    }
+
+    fn download(url: &str) -> Download {
+        Download {
+            url: url.into(),
+            sha256: String::new(),
+            args: Vec::new(),
+            file_type: None,
+            run: None,
+            extract_to: None,
+        }
+    }
+
+    #[test]
+    fn file_names_must_stay_in_the_download_folder() {
+        assert_eq!(
+            download("https://example.com/setup.exe?x=1").file_name_problem(),
+            None
+        );
+        for url in [
+            "https://example.com/..%5Csetup.exe",
+            "https://example.com/a\\..\\setup.exe",
+            "https://example.com/setup.exe:stream",
+            "https://example.com/..",
+        ] {
+            assert!(download(url).file_name_problem().is_some(), "{url}");
+        }
+    }
+
+    #[test]
+    fn fetch_reports_progress_and_hashes_the_file() {
+        let dir = download_dir("fetch");
+        let path = dir.join("app.exe");
+        let (mut sender, receiver) = iced::futures::channel::mpsc::channel(100);
+
+        let digest = block_on(fetch(&serve("200 OK"), &path, 3, &mut sender)).unwrap();
+        drop(sender);
+        assert_eq!(digest, sha256(BODY));
+        assert_eq!(std::fs::read(&path).unwrap(), BODY);
+
+        let progress: Vec<InstallProgress> = block_on(receiver.collect());
+        assert!(
+            progress.iter().any(|p| matches!(
+                p,
+                InstallProgress::Activity { index: 3, line } if line.starts_with("Downloading") && line.ends_with("%)")
+            )),
+            "{:?}",
+            progress.iter().map(line).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn matching_checksum_runs_the_installer() {
+        let (result, lines) = install_exe("good-hash", "200 OK", &sha256(BODY).to_uppercase());
+        assert!(
+            lines.contains(&"log: Checksum verified".to_string()),
+            "{lines:?}"
+        );
+        let err = result.unwrap_err();
+        assert!(err.starts_with("Failed to spawn"), "{err}");
+    }
+
+    #[test]
+    fn checksum_mismatch_runs_nothing() {
+        let (result, lines) = install_exe("bad-hash", "200 OK", &sha256(b"something else"));
+        let err = result.unwrap_err();
+        assert!(err.starts_with("Checksum mismatch"), "{err}");
+        assert!(err.ends_with(&sha256(BODY)), "{err}");
+        assert!(
+            !lines.contains(&"log: Checksum verified".to_string()),
+            "{lines:?}"
+        );
+    }
+
+    #[test]
+    fn http_error_fails_the_download() {
+        let (result, lines) = install_exe("http-error", "404 Not Found", &sha256(BODY));
+        let err = result.unwrap_err();
+        assert!(
+            err.starts_with("Download failed") && err.contains("404"),
+            "{err}"
+        );
+        assert_eq!(lines.len(), 1, "{lines:?}");
+        assert!(
+            lines[0].starts_with("log: Downloading http://"),
+            "{lines:?}"
+        );
+    }
+}
@@ -1,10 +1,16 @@
+use std::collections::{HashMap, HashSet};
+
 use iced::futures;
 use iced::futures::SinkExt as _;
 use iced::stream;
 use tokio::io::AsyncReadExt;
 use tokio::process::Command;
 
-use crate::catalog::Package;
+use crate::catalog::{self, Package, VersionPin};
+use crate::download;
+use crate::installer::{self, Installer};
+use crate::post_install;
+use crate::settings::WingetSettings;
 
 pub(crate) type Sender = futures::channel::mpsc::Sender<InstallProgress>;
 
@@ -17,6 +23,18 @@ pub enum PackageStatus {
     Cancelled,
 }
 
+/// State of one post-install step, shown below its package.
+#[derive(Debug, Clone)]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Done,
+    /// Failed, but the step has `continue_on_error`.
+    Warning(String),
+    Failed(String),
+    Skipped,
+}
+
 #[derive(Debug, Clone)]
 pub enum InstallProgress {
     Started {
@@ -34,6 +52,16 @@ pub enum InstallProgress {
         index: usize,
         line: String,
     },
+    /// A package's post-install steps, in order; all start out pending.
+    Steps {
+        index: usize,
+        labels: Vec<String>,
+    },
+    Step {
+        index: usize,
+        step: usize,
+        status: StepStatus,
+    },
     Succeeded {
         index: usize,
     },
@@ -53,38 +81,126 @@ pub(crate) enum LineEvent {
     Activity(String),
 }
 
+/// Reorder the queue so every package comes after the dependencies it shares
+/// the queue with. Otherwise keeps the original (catalog) order. Assumes the
+/// catalog has already been checked for cycles.
+pub fn order_by_dependencies(packages: Vec<Package>) -> Vec<Package> {
+    fn visit(
+        i: usize,
+        packages: &[Package],
+        index_of: &HashMap<&str, usize>,
+        placed: &mut Vec<bool>,
+        order: &mut Vec<usize>,
+    ) {
+        if placed[i] {
+            return;
+        }
+        placed[i] = true;
+        for dep in &packages[i].depends_on {
+            if let Some(&d) = index_of.get(dep.as_str()) {
+                visit(d, packages, index_of, placed, order);
+            }
+        }
+        order.push(i);
+    }
+
+    let index_of: HashMap<&str, usize> = packages
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p.id.as_str(), i))
+        .collect();
+    let mut placed = vec![false; packages.len()];
+    let mut order = Vec::with_capacity(packages.len());
+    for i in 0..packages.len() {
+        visit(i, &packages, &index_of, &mut placed, &mut order);
+    }
+
+    let mut slots: Vec<Option<Package>> = packages.into_iter().map(Some).collect();
+    order.into_iter().filter_map(|i| slots[i].take()).collect()
+}
+
 pub fn install_all(
     packages: Vec<Package>,
     dry_run: bool,
-    extra_args: Vec<String>,
+    settings: WingetSettings,
 ) -> impl futures::Stream<Item = InstallProgress> + Send {
     stream::channel(100, move |mut sender: Sender| async move {
+        // IDs of packages that failed or were skipped, so their dependents can be skipped too.
+        let mut failed: HashSet<String> = HashSet::new();
+
         for (i, pkg) in packages.iter().enumerate() {
             let _ = sender.send(InstallProgress::Started { index: i }).await;
 
-            if dry_run {
-                let cmd_desc = if let Some(ref custom) = pkg.install_command {
-                    format!("cmd /C {custom}")
-                } else if let Some(ref winget_id) = pkg.winget_id {
-                    format!("winget install --id {winget_id} -e")
+            if let Some(dep) = pkg.depends_on.iter().find(|d| failed.contains(*d)) {
+                let _ = sender
+                    .send(InstallProgress::Failed {
+                        index: i,
+                        error: format!("Skipped: dependency '{dep}' failed"),
+                    })
+                    .await;
+                failed.insert(pkg.id.clone());
+                continue;
+            }
+
+            let Some(ref installer) = pkg.installer else {
+                let _ = sender
+                    .send(InstallProgress::Failed {
+                        index: i,
+                        error: "No install method defined".into(),
+                    })
+                    .await;
+                failed.insert(pkg.id.clone());
+                continue;
+            };
+
+            let mut winget_args = settings.install_args_for(&pkg.winget);
+            if let (Some(pin), Installer::Winget { id }) = (&pkg.version, installer) {
+                let version = if dry_run {
+                    Ok(pin.to_string())
                 } else {
-                    "No install method defined".into()
+                    resolve_pinned_version(id, pin).await
                 };
+                match version {
+                    Ok(version) => {
+                        winget_args.splice(0..0, ["--version".into(), version]);
+                    }
+                    Err(error) => {
+                        let _ = sender
+                            .send(InstallProgress::Failed { index: i, error })
+                            .await;
+                        failed.insert(pkg.id.clone());
+                        continue;
+                    }
+                }
+            }
+            let command = installer.command(&winget_args);
 
+            if dry_run {
+                let action = match (installer, &command) {
+                    (Installer::Download(download), _) => download.describe(),
+                    (_, Some((program, args))) => {
+                        format!("run: {}", installer::describe(program, args))
+                    }
+                    (_, None) => "do nothing: no install method defined".into(),
+                };
                 let _ = sender
                     .send(InstallProgress::Log {
                         index: i,
-                        line: format!("[DRY RUN] Would run: {cmd_desc}"),
+                        line: format!("[DRY RUN] Would {action}"),
                     })
                     .await;
 
                 if let Some(ref post) = pkg.post_install {
-                    let _ = sender
-                        .send(InstallProgress::Log {
-                            index: i,
-                            line: format!("[DRY RUN] Would run post-install: {post}"),
-                        })
-                        .await;
+                    let steps = post.steps();
+                    for (n, step) in steps.iter().enumerate() {
+                        let line = format!(
+                            "[DRY RUN] Would {} (post-install step {}/{})",
+                            step.describe(),
+                            n + 1,
+                            steps.len()
+                        );
+                        let _ = sender.send(InstallProgress::Log { index: i, line }).await;
+                    }
                 }
 
                 tokio::time::sleep(std::time::Duration::from_millis(150)).await;
@@ -92,54 +208,31 @@ pub fn install_all(
                 continue;
             }
 
-            let (program, args) = if let Some(ref custom) = pkg.install_command {
-                ("cmd".to_string(), vec!["/C".to_string(), custom.clone()])
-            } else if let Some(ref winget_id) = pkg.winget_id {
-                let mut a = vec![
-                    "install".into(),
-                    "--id".into(),
-                    winget_id.clone(),
-                    "-e".into(),
-                    "--accept-package-agreements".into(),
-                    "--accept-source-agreements".into(),
-                ];
-                a.extend(extra_args.iter().cloned());
-                ("winget".to_string(), a)
-            } else {
-                let _ = sender
-                    .send(InstallProgress::Failed {
-                        index: i,
-                        error: "No install method defined".into(),
-                    })
-                    .await;
-                continue;
+            let result = match (installer, command) {
+                (Installer::Download(download), _) => {
+                    download::install(download, i, &mut sender).await
+                }
+                (_, Some((program, args))) => run_command(&program, &args, i, &mut sender).await,
+                (Installer::Unsupported, _) => {
+                    Err("Installer type not supported by this version of Provision".into())
+                }
+                (_, None) => Err("No install method defined".into()),
             };
 
-            match run_command(&program, &args, i, &mut sender).await {
+            let result = match (result, &pkg.post_install) {
+                (Ok(()), Some(post)) => post_install::run(post, i, &mut sender).await,
+                (result, _) => result,
+            };
+
+            match result {
                 Ok(()) => {
-                    if let Some(ref post) = pkg.post_install {
-                        let _ = sender
-                            .send(InstallProgress::Log {
-                                index: i,
-                                line: format!("Running post-install: {post}"),
-                            })
-                            .await;
-                        let post_args = vec!["/C".to_string(), post.clone()];
-                        if let Err(e) = run_command("cmd", &post_args, i, &mut sender).await {
-                            let _ = sender
-                                .send(InstallProgress::Log {
-                                    index: i,
-                                    line: format!("Post-install warning: {e}"),
-                                })
-                                .await;
-                        }
-                    }
                     let _ = sender.send(InstallProgress::Succeeded { index: i }).await;
                 }
                 Err(e) => {
                     let _ = sender
                         .send(InstallProgress::Failed { index: i, error: e })
                         .await;
+                    failed.insert(pkg.id.clone());
                 }
             }
         }
@@ -147,6 +240,42 @@ pub fn install_all(
     })
 }
 
+/// The version to install for a pinned package. Exact pins are used as-is;
+/// `.*` pins resolve to the newest matching version winget knows about.
+async fn resolve_pinned_version(winget_id: &str, pin: &VersionPin) -> Result<String, String> {
+    if let Some(version) = pin.exact() {
+        return Ok(version.to_string());
+    }
+
+    let output = Command::new("winget")
+        .args(["show", "--id", winget_id, "-e", "--versions"])
+        .args(["--accept-source-agreements"])
+        .stderr(std::process::Stdio::null())
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .await
+        .map_err(|e| format!("Failed to spawn: {e}"))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_versions_list(&stdout)
+        .into_iter()
+        .filter(|v| pin.matches(v))
+        .max_by(|a, b| catalog::compare_versions(a, b))
+        .ok_or_else(|| format!("No available version matches pin {pin}"))
+}
+
+/// Versions listed by `winget show --versions`, below the `---` separator.
+fn parse_versions_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .skip_while(|l| !l.starts_with("---"))
+        .skip(1)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect()
+}
+
 /// Returns true if a line is transient terminal output (spinners, progress bars, etc.)
 /// that should overwrite the live line rather than be appended to the log.
 fn is_transient(line: &str) -> bool {
@@ -266,11 +395,25 @@ pub(crate) async fn run_command(
     index: usize,
     sender: &mut Sender,
 ) -> Result<(), String> {
-    let mut child = Command::new(program)
-        .args(args)
+    let mut command = Command::new(program);
+    command.args(args);
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    match run_process(command, index, sender).await? {
+        0 => Ok(()),
+        code => Err(format!("Exit code: {code}")),
+    }
+}
+
+/// Spawn `command` with its stdout streamed into the log, and return its
+/// exit code.
+pub(crate) async fn run_process(
+    mut command: Command,
+    index: usize,
+    sender: &mut Sender,
+) -> Result<i32, String> {
+    let mut child = command
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::null())
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
         .spawn()
         .map_err(|e| format!("Failed to spawn: {e}"))?;
 
@@ -290,9 +433,5 @@ pub(crate) async fn run_command(
         .await
         .map_err(|e| format!("Wait failed: {e}"))?;
 
-    if status.success() {
-        Ok(())
-    } else {
-        Err(format!("Exit code: {}", status.code().unwrap_or(-1)))
-    }
+    Ok(status.code().unwrap_or(-1))
 }
@@ -0,0 +1,322 @@
+//! Install backends: how a package is installed and how to tell whether it
+//! already is.
+//!
+//! A package declares its backend with an inline table, e.g.
+//! `installer = { type = "scoop", name = "ripgrep", bucket = "main" }`.
+//! The older `winget_id` and `install_command` fields are shorthand for the
+//! `winget` and `command` backends.
+
+use std::fmt;
+
+use tokio::process::Command;
+
+pub use crate::catalog_types::Installer;
+
+/// The kind of an `Installer`, without its package details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    Winget,
+    Msstore,
+    Scoop,
+    Choco,
+    Cargo,
+    Npm,
+    Pipx,
+    PowershellModule,
+    Command,
+    Download,
+    Unsupported,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Winget => "winget",
+            Self::Msstore => "msstore",
+            Self::Scoop => "scoop",
+            Self::Choco => "choco",
+            Self::Cargo => "cargo",
+            Self::Npm => "npm",
+            Self::Pipx => "pipx",
+            Self::PowershellModule => "powershell-module",
+            Self::Command => "command",
+            Self::Download => "download",
+            Self::Unsupported => "unsupported",
+        })
+    }
+}
+
+impl Installer {
+    pub fn backend(&self) -> Backend {
+        match self {
+            Self::Winget { .. } => Backend::Winget,
+            Self::Msstore { .. } => Backend::Msstore,
+            Self::Scoop { .. } => Backend::Scoop,
+            Self::Choco { .. } => Backend::Choco,
+            Self::Cargo { .. } => Backend::Cargo,
+            Self::Npm { .. } => Backend::Npm,
+            Self::Pipx { .. } => Backend::Pipx,
+            Self::PowershellModule { .. } => Backend::PowershellModule,
+            Self::Command { .. } => Backend::Command,
+            Self::Download(_) => Backend::Download,
+            Self::Unsupported => Backend::Unsupported,
+        }
+    }
+
+    /// The package's identifier within its backend; `None` for commands and
+    /// downloads.
+    pub fn package_name(&self) -> Option<&str> {
+        match self {
+            Self::Winget { id } | Self::Msstore { id } => Some(id),
+            Self::Scoop { name, .. }
+            | Self::Choco { name }
+            | Self::Cargo { name }
+            | Self::Npm { name }
+            | Self::Pipx { name }
+            | Self::PowershellModule { name } => Some(name),
+            Self::Command { .. } | Self::Download(_) | Self::Unsupported => None,
+        }
+    }
+
+    /// Key into the installed-package map: the lowercase winget ID for
+    /// winget and Store packages (both show up in `winget list`), otherwise
+    /// `backend:name`. `None` for commands and downloads, which can't be
+    /// detected.
+    pub fn installed_key(&self) -> Option<String> {
+        let name = self.package_name()?.to_lowercase();
+        match self.backend() {
+            Backend::Winget | Backend::Msstore => Some(name),
+            backend => Some(format!("{backend}:{name}")),
+        }
+    }
+
+    /// Program and arguments that install this package. `winget_args` (install
+    /// mode, scope, version, ...) only apply to the winget and Store backends.
+    /// `None` for downloads, which `download::install` runs in several steps,
+    /// and for unsupported types.
+    pub fn command(&self, winget_args: &[String]) -> Option<(String, Vec<String>)> {
+        let winget = |id: &str, source: Option<&str>| {
+            let mut args = vec!["install".into(), "--id".into(), id.into(), "-e".into()];
+            if let Some(source) = source {
+                args.push("--source".into());
+                args.push(source.into());
+            }
+            args.push("--accept-package-agreements".into());
+            args.push("--accept-source-agreements".into());
+            args.extend(winget_args.iter().cloned());
+            Some(("winget".to_string(), args))
+        };
+        let program = |program: &str, args: &[&str]| {
+            let args = args.iter().map(|a| a.to_string()).collect();
+            Some((program.to_string(), args))
+        };
+
+        match self {
+            Self::Winget { id } => winget(id, None),
+            Self::Msstore { id } => winget(id, Some("msstore")),
+            // Scoop and npm are script shims (.cmd/.ps1), so they go through cmd
+            Self::Scoop { name, bucket: None } => {
+                program("cmd", &["/C", &format!("scoop install {name}")])
+            }
+            Self::Scoop {
+                name,
+                bucket: Some(bucket),
+            } => program(
+                "cmd",
+                &[
+                    "/C",
+                    &format!("scoop bucket add {bucket} & scoop install {bucket}/{name}"),
+                ],
+            ),
+            Self::Choco { name } => program("choco", &["install", name, "-y", "--no-progress"]),
+            Self::Cargo { name } => program("cargo", &["install", name]),
+            Self::Npm { name } => program("cmd", &["/C", &format!("npm install -g {name}")]),
+            Self::Pipx { name } => program("pipx", &["install", name]),
+            Self::PowershellModule { name } => program(
+                "powershell",
+                &[
+                    "-NoProfile",
+                    "-NonInteractive",
+                    "-Command",
+                    &format!("Install-Module -Name {name} -Force"),
+                ],
+            ),
+            Self::Command { command } => program("cmd", &["/C", command]),
+            Self::Download(_) | Self::Unsupported => None,
+        }
+    }
+
+    /// Program and arguments that remove this package. `winget_args` only
+    /// apply to the winget and Store backends. `None` for commands and
+    /// downloads, which need the package's own `uninstall_command`.
+    pub fn uninstall_command(&self, winget_args: &[String]) -> Option<(String, Vec<String>)> {
+        let program = |program: &str, args: &[&str]| {
+            let args = args.iter().map(|a| a.to_string()).collect();
+            Some((program.to_string(), args))
+        };
+
+        match self {
+            Self::Winget { id } => Some(winget_uninstall(id, winget_args)),
+            Self::Msstore { id } => {
+                let (program, mut args) = winget_uninstall(id, winget_args);
+                args.splice(4..4, ["--source".into(), "msstore".into()]);
+                Some((program, args))
+            }
+            Self::Scoop { name, .. } => program("cmd", &["/C", &format!("scoop uninstall {name}")]),
+            Self::Choco { name } => program("choco", &["uninstall", name, "-y"]),
+            Self::Cargo { name } => program("cargo", &["uninstall", name]),
+            Self::Npm { name } => program("cmd", &["/C", &format!("npm uninstall -g {name}")]),
+            Self::Pipx { name } => program("pipx", &["uninstall", name]),
+            Self::PowershellModule { name } => program(
+                "powershell",
+                &[
+                    "-NoProfile",
+                    "-NonInteractive",
+                    "-Command",
+                    &format!("Uninstall-Module -Name {name} -AllVersions -Force"),
+                ],
+            ),
+            Self::Command { .. } | Self::Download(_) | Self::Unsupported => None,
+        }
+    }
+
+    /// Short description for the review screen: the winget ID or command as
+    /// before, otherwise e.g. `scoop: ripgrep`.
+    pub fn summary(&self) -> String {
+        match self {
+            Self::Winget { id } => id.clone(),
+            Self::Command { command } => command.clone(),
+            Self::Download(download) => format!("download: {}", download.file_name()),
+            Self::Unsupported => "unsupported installer".into(),
+            Self::Scoop {
+                name,
+                bucket: Some(bucket),
+            } => format!("scoop: {bucket}/{name}"),
+            other => format!(
+                "{}: {}",
+                other.backend(),
+                other.package_name().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// `winget uninstall` for an exact ID, e.g. one found by `winget list`.
+pub fn winget_uninstall(id: &str, winget_args: &[String]) -> (String, Vec<String>) {
+    let mut args: Vec<String> = vec![
+        "uninstall".into(),
+        "--id".into(),
+        id.into(),
+        "-e".into(),
+        "--accept-source-agreements".into(),
+    ];
+    args.extend(winget_args.iter().cloned());
+    ("winget".into(), args)
+}
+
+/// A command line as it would be typed, for dry-run logs. Arguments with
+/// spaces are quoted unless they already carry their own quotes.
+pub fn describe(program: &str, args: &[String]) -> String {
+    let mut line = program.to_string();
+    for arg in args {
+        line.push(' ');
+        if arg.contains(' ') && !arg.contains('"') {
+            line.push_str(&format!("\"{arg}\""));
+        } else {
+            line.push_str(arg);
+        }
+    }
+    line
+}
+
+impl Backend {
+    /// Command listing what this backend has installed. Winget and Store
+    /// packages come from the `winget list` scan; commands can't be listed.
+    fn list_command(self) -> Option<(&'static str, &'static [&'static str])> {
+        match self {
+            Self::Winget | Self::Msstore | Self::Command | Self::Download | Self::Unsupported => {
+                None
+            }
+            Self::Scoop => Some(("cmd", &["/C", "scoop list"])),
+            Self::Choco => Some(("choco", &["list", "--limit-output"])),
+            Self::Cargo => Some(("cargo", &["install", "--list"])),
+            Self::Npm => Some(("cmd", &["/C", "npm ls -g --depth=0 --parseable"])),
+            Self::Pipx => Some(("pipx", &["list", "--short"])),
+            Self::PowershellModule => Some((
+                "powershell",
+                &[
+                    "-NoProfile",
+                    "-NonInteractive",
+                    "-Command",
+                    "Get-Module -ListAvailable | ForEach-Object { \"$($_.Name) $($_.Version)\" }",
+                ],
+            )),
+        }
+    }
+
+    /// Parse the output of `list_command` into `(name, version)` pairs.
+    /// The version is empty where the backend doesn't report one.
+    fn parse_list(self, output: &str) -> Vec<(String, String)> {
+        let lines = output.lines().map(str::trim).filter(|l| !l.is_empty());
+        let name_version = |line: &str| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next().unwrap_or_default().to_string();
+            (name, parts.next().unwrap_or_default().to_string())
+        };
+
+        match self {
+            // Table below a `----` rule: Name Version Source Updated Info
+            Self::Scoop => lines
+                .skip_while(|l| !l.starts_with("----"))
+                .skip(1)
+                .map(name_version)
+                .collect(),
+            // `name|version`
+            Self::Choco => lines
+                .filter_map(|l| l.split_once('|'))
+                .map(|(name, version)| (name.to_string(), version.to_string()))
+                .collect(),
+            // `ripgrep v14.1.0:` followed by indented binary names
+            Self::Cargo => output
+                .lines()
+                .filter(|l| !l.starts_with(char::is_whitespace) && l.ends_with(':'))
+                .map(|l| {
+                    let (name, version) = name_version(l.trim_end_matches(':'));
+                    (name, version.trim_start_matches('v').to_string())
+                })
+                .collect(),
+            // One path per package: `...\node_modules\@scope\name`
+            Self::Npm => lines
+                .filter_map(|l| l.rsplit_once("node_modules\\"))
+                .map(|(_, name)| (name.replace('\\', "/"), String::new()))
+                .collect(),
+            Self::Pipx | Self::PowershellModule => lines.map(name_version).collect(),
+            Self::Winget | Self::Msstore | Self::Command | Self::Download | Self::Unsupported => {
+                Vec::new()
+            }
+        }
+    }
+
+    /// Ask this backend what it has installed, as installed-map keys and
+    /// versions. Returns nothing if the tool is missing or fails.
+    pub async fn scan_installed(self) -> Vec<(String, String)> {
+        let Some((program, args)) = self.list_command() else {
+            return Vec::new();
+        };
+        let output = Command::new(program)
+            .args(args)
+            .stderr(std::process::Stdio::null())
+            .creation_flags(0x08000000) // CREATE_NO_WINDOW
+            .output()
+            .await;
+
+        match output {
+            Ok(output) if output.status.success() => self
+                .parse_list(&String::from_utf8_lossy(&output.stdout))
+                .into_iter()
+                .map(|(name, version)| (format!("{self}:{}", name.to_lowercase()), version))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
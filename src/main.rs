@@ -1,9 +1,22 @@
 mod catalog;
+// Shared with build.rs and the catalog tools
+#[allow(dead_code)]
+mod catalog_merge;
+// Shared with lint_packages
+mod catalog_types;
+mod details;
+mod detect;
+mod download;
 mod install;
+mod installer;
+mod post_install;
 mod profile;
+mod search;
 mod settings;
 mod styles;
+mod system;
 mod theme;
+mod uninstall;
 mod upgrade;
 mod views;
 
@@ -11,17 +24,154 @@ use std::collections::{HashMap, HashSet};
 
 use iced::{Element, Size, Task, Theme, clipboard, keyboard, task};
 
-use catalog::{CatalogSource, Package};
-use install::PackageStatus;
-use profile::Profile;
+use catalog::{Catalog, CatalogError, CatalogRefresh, CatalogSource, Package};
+use detect::Detect;
+use install::{PackageStatus, StepStatus};
+use installer::Backend;
 use upgrade::UpgradeablePackage;
 
 impl App {
     /// Check whether a package from the catalog is already installed.
     pub(crate) fn is_installed(&self, pkg: &Package) -> bool {
-        pkg.winget_id_lower
+        pkg.installed_key
             .as_ref()
-            .is_some_and(|wid| self.installed.contains_key(wid))
+            .is_some_and(|key| self.installed.contains_key(key))
+    }
+
+    /// Installed, and the catalog says how to remove it.
+    pub(crate) fn is_removable(&self, pkg: &Package) -> bool {
+        self.is_installed(pkg) && pkg.uninstaller(&[]).is_some()
+    }
+
+    /// Whether a package's checkbox is ticked in the picker: picked for
+    /// install, or for removal in uninstall mode.
+    pub(crate) fn is_checked(&self, pkg: &Package) -> bool {
+        if self.uninstall_mode {
+            pkg.installed_key
+                .as_ref()
+                .is_some_and(|key| self.uninstall_selected.contains(key))
+        } else {
+            self.selected.contains(&pkg.id)
+        }
+    }
+
+    /// Software found by `winget list` that isn't in the catalog, by name.
+    pub(crate) fn other_installed(&self) -> Vec<&upgrade::InstalledPackage> {
+        let catalog_keys: HashSet<&str> = self
+            .catalog
+            .packages
+            .iter()
+            .filter_map(|p| p.installed_key.as_deref())
+            .collect();
+        let mut others: Vec<_> = self
+            .installed
+            .values()
+            .filter(|p| !p.key.contains(':') && !catalog_keys.contains(p.key.as_str()))
+            .collect();
+        others.sort_by_key(|p| p.name.to_lowercase());
+        others
+    }
+
+    /// The uninstall queue: selected catalog packages, dependents before
+    /// their dependencies, then other selected software.
+    pub(crate) fn removals(&self) -> Vec<uninstall::Removal> {
+        let winget_args = self.settings.winget.uninstall_args();
+        let packages: Vec<Package> = self
+            .catalog
+            .packages
+            .iter()
+            .filter(|p| {
+                p.installed_key
+                    .as_ref()
+                    .is_some_and(|key| self.uninstall_selected.contains(key))
+            })
+            .cloned()
+            .collect();
+
+        let mut removals: Vec<uninstall::Removal> = install::order_by_dependencies(packages)
+            .iter()
+            .rev()
+            .filter_map(|p| {
+                let key = p.installed_key.as_deref()?;
+                Some(uninstall::Removal::for_package(p, key, &winget_args))
+            })
+            .collect();
+        removals.extend(
+            self.other_installed()
+                .into_iter()
+                .filter(|p| self.uninstall_selected.contains(&p.key))
+                .map(|p| uninstall::Removal::for_installed(p, &winget_args)),
+        );
+        removals
+    }
+
+    /// Match a package against the picker's filters: it must carry every
+    /// active tag and, if there is a search query, match it.
+    pub(crate) fn package_match(
+        &self,
+        pkg: &Package,
+        query: &search::Query,
+    ) -> Option<search::Match> {
+        if !self.active_tags.iter().all(|tag| pkg.tags.contains(tag)) {
+            return None;
+        }
+        if query.is_empty() {
+            return Some(search::Match::default());
+        }
+        pkg.search_match(query)
+    }
+
+    /// False if the package declares constraints this machine doesn't meet.
+    pub(crate) fn is_compatible(&self, pkg: &Package) -> bool {
+        pkg.incompatibility(&self.system).is_none()
+    }
+
+    /// The catalog package, or one added from winget search, that a
+    /// `winget search` result refers to.
+    pub(crate) fn search_result_package(&self, result: &upgrade::SearchResult) -> Option<&Package> {
+        let key = result.id.to_lowercase();
+        self.catalog
+            .packages
+            .iter()
+            .find(|p| p.installed_key.as_deref() == Some(key.as_str()))
+    }
+
+    fn toggle_package(&mut self, id: String) {
+        if !self.selected.remove(&id) {
+            self.selected.insert(id);
+            self.select_dependencies();
+            return;
+        }
+        // Packages that need this one can't be installed without it, unless
+        // it is already there
+        let installed = self
+            .catalog
+            .packages
+            .iter()
+            .any(|p| p.id == id && self.is_installed(p));
+        if !installed {
+            for pkg in catalog::dependents(&self.catalog.packages, &id) {
+                self.selected.remove(&pkg.id);
+            }
+        }
+    }
+
+    /// Add dependencies of the current selection that aren't installed yet,
+    /// then drop anything this machine can't run.
+    fn select_dependencies(&mut self) {
+        let missing: Vec<String> =
+            catalog::missing_dependencies(&self.catalog.packages, &self.selected)
+                .into_iter()
+                .filter(|p| !self.is_installed(p))
+                .map(|p| p.id.clone())
+                .collect();
+        self.selected.extend(missing);
+
+        for pkg in &self.catalog.packages {
+            if !self.is_compatible(pkg) {
+                self.selected.remove(&pkg.id);
+            }
+        }
     }
 }
 
@@ -95,6 +245,8 @@ fn main() -> iced::Result {
 #[derive(Default)]
 pub(crate) struct ProgressState {
     pub(crate) statuses: Vec<PackageStatus>,
+    /// Post-install steps per package, as (label, status).
+    pub(crate) steps: Vec<Vec<(String, StepStatus)>>,
     pub(crate) current: usize,
     pub(crate) log: Vec<String>,
     pub(crate) live_line: String,
@@ -108,6 +260,7 @@ const LOG_CAP: usize = 200;
 impl ProgressState {
     fn start(&mut self, count: usize) {
         self.statuses = vec![PackageStatus::Pending; count];
+        self.steps = vec![Vec::new(); count];
         self.current = 0;
         self.log.clear();
         self.live_line.clear();
@@ -142,6 +295,23 @@ impl ProgressState {
             install::InstallProgress::Activity { line, .. } => {
                 self.live_line = line.clone();
             }
+            install::InstallProgress::Steps { index, labels } => {
+                if let Some(steps) = self.steps.get_mut(*index) {
+                    *steps = labels
+                        .iter()
+                        .map(|label| (label.clone(), StepStatus::Pending))
+                        .collect();
+                }
+            }
+            install::InstallProgress::Step {
+                index,
+                step,
+                status,
+            } => {
+                if let Some((_, s)) = self.steps.get_mut(*index).and_then(|s| s.get_mut(*step)) {
+                    *s = status.clone();
+                }
+            }
             install::InstallProgress::Succeeded { index } => {
                 if let Some(s) = self.statuses.get_mut(*index) {
                     *s = PackageStatus::Done;
@@ -152,6 +322,7 @@ impl ProgressState {
                 if let Some(s) = self.statuses.get_mut(*index) {
                     *s = PackageStatus::Failed(error.clone());
                 }
+                self.skip_pending_steps(*index);
                 self.live_line.clear();
             }
             install::InstallProgress::Completed => {
@@ -162,6 +333,15 @@ impl ProgressState {
         }
     }
 
+    /// Mark steps that never ran (after a failed step) as skipped.
+    fn skip_pending_steps(&mut self, index: usize) {
+        for (_, s) in self.steps.get_mut(index).into_iter().flatten() {
+            if matches!(s, StepStatus::Pending | StepStatus::Running) {
+                *s = StepStatus::Skipped;
+            }
+        }
+    }
+
     fn cancel(&mut self, label: &str) {
         self._handle = None;
         self.copy_status = false;
@@ -170,6 +350,9 @@ impl ProgressState {
                 *s = PackageStatus::Cancelled;
             }
         }
+        for i in 0..self.steps.len() {
+            self.skip_pending_steps(i);
+        }
         self.done = true;
         self.live_line.clear();
         self.log.push(String::new());
@@ -208,41 +391,98 @@ pub(crate) struct UpdateScanState {
     pub(crate) _handle: Option<task::Handle>,
 }
 
+/// `winget search` from the package picker.
+#[derive(Default)]
+pub(crate) struct WingetSearchState {
+    /// The query the results are for.
+    pub(crate) query: String,
+    pub(crate) running: bool,
+    pub(crate) results: Vec<upgrade::SearchResult>,
+    pub(crate) error: Option<String>,
+}
+
+/// Result of the last catalog check or source change, for the Catalog
+/// settings tab.
+#[derive(Debug, Clone)]
+pub(crate) enum CatalogStatus {
+    Success(String),
+    Error(String),
+}
+
 pub(crate) struct App {
     pub(crate) dry_run: bool,
-    pub(crate) selected_profile: Option<Profile>,
+    /// Slug of the profile picked on the home screen.
+    pub(crate) selected_profile: Option<String>,
     pub(crate) screen: Screen,
-    pub(crate) catalog: Vec<Package>,
+    pub(crate) catalog: Catalog,
     pub(crate) catalog_source: CatalogSource,
+    /// A catalog revalidation request is in flight.
+    pub(crate) catalog_refreshing: bool,
+    /// Result of the last catalog check, shown on the Catalog settings tab.
+    pub(crate) catalog_status: Option<CatalogStatus>,
+    /// Why the last refresh couldn't reach any catalog source, shown next to
+    /// the catalog indicator on the home screen.
+    pub(crate) catalog_unreachable: Option<String>,
+    /// Text field for adding a catalog source in settings.
+    pub(crate) new_catalog_source: String,
+    /// Optional public key for the catalog source being added.
+    pub(crate) new_catalog_source_key: String,
     pub(crate) selected: HashSet<String>,
     pub(crate) search: String,
-    pub(crate) settings: settings::WingetSettings,
+    /// Tag filter chips switched on in the package picker; all must match.
+    pub(crate) active_tags: HashSet<String>,
+    /// Package whose details panel is open in the picker.
+    pub(crate) details_open: Option<String>,
+    /// `winget show` results by package ID; `None` while the fetch runs.
+    pub(crate) winget_details: HashMap<String, Option<Result<details::WingetDetails, String>>>,
+    pub(crate) winget_search: WingetSearchState,
+    pub(crate) settings: settings::Settings,
     pub(crate) settings_tab: settings::SettingsTab,
     // Install state
     pub(crate) install_queue: Vec<Package>,
     pub(crate) install: ProgressState,
-    /// Installed packages detected at startup: winget_id (lowercase) -> version
-    pub(crate) installed: HashMap<String, String>,
+    /// Installed packages detected at startup, by installed key (see
+    /// `Installer::installed_key`)
+    pub(crate) installed: HashMap<String, upgrade::InstalledPackage>,
     pub(crate) installed_scan_done: bool,
     pub(crate) _installed_scan_handle: Option<task::Handle>,
+    /// Backends and detect rules the installed scans have covered, so a
+    /// new catalog only rescans what it adds.
+    pub(crate) scanned_backends: HashSet<Backend>,
+    pub(crate) scanned_detect_rules: HashMap<String, Detect>,
+    /// Architecture and Windows build, detected at startup.
+    pub(crate) system: system::SystemInfo,
     // Update scan + upgrade state
     pub(crate) update_scan: UpdateScanState,
     pub(crate) upgrade_queue: Vec<UpgradeablePackage>,
     pub(crate) upgrade: ProgressState,
+    // Uninstall state
+    /// The package picker lists installed software to remove instead.
+    pub(crate) uninstall_mode: bool,
+    /// Installed keys picked for removal.
+    pub(crate) uninstall_selected: HashSet<String>,
+    pub(crate) uninstall_queue: Vec<uninstall::Removal>,
+    pub(crate) uninstall: ProgressState,
     /// Transient status message for export/import feedback.
     pub(crate) selection_status: Option<String>,
 }
 
 impl App {
     fn new(dry_run: bool) -> (Self, Task<Message>) {
+        let catalog = catalog::load_catalog();
+        let backends = catalog.backends();
+        let detect_rules = catalog.detect_rules();
+        let scanned_backends = backends.iter().copied().collect();
+        let scanned_detect_rules = detect_rules.iter().cloned().collect();
         let (scan_task, scan_handle) = Task::run(
-            upgrade::scan_installed(dry_run),
+            upgrade::scan_installed(dry_run, backends, detect_rules),
             Message::InstalledScanProgress,
         )
         .abortable();
 
+        let user_settings = settings::load_settings();
         let catalog_task = Task::perform(
-            catalog::fetch_remote_catalog(dry_run),
+            catalog::load_cached_catalog(user_settings.catalog.enabled_sources(), dry_run),
             Message::CatalogFetched,
         );
 
@@ -251,23 +491,43 @@ impl App {
                 dry_run,
                 selected_profile: None,
                 screen: Screen::default(),
-                catalog: catalog::load_catalog(),
+                catalog,
                 catalog_source: CatalogSource::Embedded,
+                catalog_refreshing: false,
+                catalog_status: None,
+                catalog_unreachable: None,
+                new_catalog_source: String::new(),
+                new_catalog_source_key: String::new(),
                 selected: HashSet::new(),
                 search: String::new(),
-                settings: settings::load_settings(),
+                active_tags: HashSet::new(),
+                details_open: None,
+                winget_details: HashMap::new(),
+                winget_search: WingetSearchState::default(),
+                settings: user_settings,
                 settings_tab: settings::SettingsTab::default(),
                 install_queue: Vec::new(),
                 install: ProgressState::default(),
                 installed: HashMap::new(),
                 installed_scan_done: false,
                 _installed_scan_handle: Some(scan_handle.abort_on_drop()),
+                scanned_backends,
+                scanned_detect_rules,
+                system: system::SystemInfo::default(),
                 update_scan: UpdateScanState::default(),
                 upgrade_queue: Vec::new(),
                 upgrade: ProgressState::default(),
+                uninstall_mode: false,
+                uninstall_selected: HashSet::new(),
+                uninstall_queue: Vec::new(),
+                uninstall: ProgressState::default(),
                 selection_status: None,
             },
-            Task::batch([scan_task, catalog_task]),
+            Task::batch([
+                scan_task,
+                catalog_task,
+                Task::perform(system::detect(), Message::SystemDetected),
+            ]),
         )
     }
 }
@@ -282,17 +542,30 @@ pub(crate) enum Screen {
     UpdateScanning,
     UpdateSelect,
     Updating,
+    Uninstalling,
     Settings,
 }
 
 #[derive(Debug, Clone)]
 pub(crate) enum Message {
-    CatalogFetched(Result<(Vec<Package>, CatalogSource), String>),
+    CatalogFetched(Result<(Catalog, CatalogSource), CatalogError>),
+    SystemDetected(system::SystemInfo),
+    CatalogRefreshed(Result<CatalogRefresh, CatalogError>),
+    RefreshCatalog,
     InstalledScanProgress(upgrade::InstalledScanProgress),
-    ProfileSelected(Profile),
+    CatalogInstalledScanned(Vec<upgrade::InstalledPackage>),
+    ProfileSelected(String),
     GoBack,
     TogglePackage(String),
     SearchChanged(String),
+    ToggleTag(String),
+    ShowDetails(String),
+    CloseDetails,
+    DetailsFetched(String, Result<details::WingetDetails, String>),
+    OpenUrl(String),
+    SearchWinget,
+    WingetSearchCompleted(String, Result<Vec<upgrade::SearchResult>, String>),
+    ToggleWingetResult(usize),
     GoToReview,
     StartInstall,
     CancelInstall,
@@ -306,6 +579,12 @@ pub(crate) enum Message {
     CancelUpgrade,
     UpgradeProgress(install::InstallProgress),
     FinishUpdateAndReset,
+    ToggleUninstallMode,
+    ToggleUninstallPackage(String),
+    StartUninstall,
+    CancelUninstall,
+    UninstallProgress(install::InstallProgress),
+    FinishUninstallAndReset,
     ToggleCategory(String),
     ExportSelection,
     ExportCompleted(Result<(), String>),
@@ -324,6 +603,12 @@ pub(crate) enum Message {
     ToggleIgnoreSecurityHash(bool),
     ToggleDisableInteractivity(bool),
     SetInstallLocation(String),
+    ToggleCatalogSource(usize, bool),
+    MoveCatalogSource(usize, usize),
+    RemoveCatalogSource(usize),
+    NewCatalogSourceChanged(String),
+    NewCatalogSourceKeyChanged(String),
+    AddCatalogSource,
     KeyConfirm,
     KeyEscape,
     SelectAll,
@@ -357,7 +642,20 @@ impl App {
         match message {
             // ── Domain handlers ──────────────────────────────────────
             Message::CatalogFetched(r) => self.handle_catalog_fetched(r),
+            Message::SystemDetected(info) => {
+                self.system = info;
+                self.select_dependencies();
+                Task::none()
+            }
+            Message::CatalogRefreshed(r) => self.handle_catalog_refreshed(r),
+            Message::RefreshCatalog => self.start_catalog_refresh(true),
             Message::InstalledScanProgress(e) => self.handle_installed_scan_progress(e),
+            Message::CatalogInstalledScanned(packages) => {
+                for pkg in packages {
+                    self.installed.insert(pkg.key.clone(), pkg);
+                }
+                Task::none()
+            }
             Message::ProfileSelected(p) => self.handle_profile_selected(p),
             Message::GoBack => self.handle_go_back(),
             Message::StartInstall => self.handle_start_install(),
@@ -371,6 +669,10 @@ impl App {
             Message::CancelUpgrade => self.handle_cancel_upgrade(),
             Message::UpgradeProgress(e) => self.handle_upgrade_progress(e),
             Message::FinishUpdateAndReset => self.handle_finish_update_and_reset(),
+            Message::StartUninstall => self.handle_start_uninstall(),
+            Message::CancelUninstall => self.handle_cancel_uninstall(),
+            Message::UninstallProgress(e) => self.handle_uninstall_progress(e),
+            Message::FinishUninstallAndReset => self.handle_finish_uninstall_and_reset(),
             Message::ToggleCategory(cat) => self.handle_toggle_category(cat),
             Message::SelectAll => self.handle_select_all(),
             Message::ExportSelection => self.handle_export_selection(),
@@ -380,10 +682,24 @@ impl App {
             Message::CopyLog(lines) => self.handle_copy_log(lines),
             Message::KeyConfirm => self.handle_key_confirm(),
             Message::KeyEscape => self.handle_key_escape(),
+            Message::ShowDetails(id) => self.handle_show_details(id),
+            Message::SearchWinget => self.handle_search_winget(),
+            Message::WingetSearchCompleted(query, r) => {
+                self.handle_winget_search_completed(query, r)
+            }
+            Message::ToggleWingetResult(i) => self.handle_toggle_winget_result(i),
             // ── Inline one-liners ────────────────────────────────────
             Message::TogglePackage(id) => {
-                if !self.selected.remove(&id) {
-                    self.selected.insert(id);
+                self.toggle_package(id);
+                Task::none()
+            }
+            Message::ToggleUninstallMode => {
+                self.uninstall_mode = !self.uninstall_mode;
+                Task::none()
+            }
+            Message::ToggleUninstallPackage(key) => {
+                if !self.uninstall_selected.remove(&key) {
+                    self.uninstall_selected.insert(key);
                 }
                 Task::none()
             }
@@ -397,6 +713,21 @@ impl App {
                 self.search = v;
                 Task::none()
             }
+            Message::ToggleTag(tag) => {
+                if !self.active_tags.remove(&tag) {
+                    self.active_tags.insert(tag);
+                }
+                Task::none()
+            }
+            Message::CloseDetails => {
+                self.details_open = None;
+                Task::none()
+            }
+            Message::DetailsFetched(id, result) => {
+                self.winget_details.insert(id, Some(result));
+                Task::none()
+            }
+            Message::OpenUrl(url) => Task::perform(details::open_url(url), Message::Noop),
             Message::GoToReview => {
                 self.screen = Screen::Review;
                 Task::none()
@@ -417,40 +748,69 @@ impl App {
             Message::ClearCopyStatus => {
                 self.install.copy_status = false;
                 self.upgrade.copy_status = false;
+                self.uninstall.copy_status = false;
                 Task::none()
             }
             Message::SetInstallMode(mode) => {
-                self.settings.install_mode = mode;
+                self.settings.winget.install_mode = mode;
                 self.save_settings()
             }
             Message::SetScope(opt) => {
-                self.settings.scope = opt.0;
+                self.settings.winget.scope = opt.0;
                 self.save_settings()
             }
             Message::SetArchitecture(opt) => {
-                self.settings.architecture = opt.0;
+                self.settings.winget.architecture = opt.0;
                 self.save_settings()
             }
             Message::ToggleForce(v) => {
-                self.settings.force = v;
+                self.settings.winget.force = v;
                 self.save_settings()
             }
             Message::ToggleIncludeUnknown(v) => {
-                self.settings.include_unknown = v;
+                self.settings.winget.include_unknown = v;
                 self.save_settings()
             }
             Message::ToggleIgnoreSecurityHash(v) => {
-                self.settings.ignore_security_hash = v;
+                self.settings.winget.ignore_security_hash = v;
                 self.save_settings()
             }
             Message::ToggleDisableInteractivity(v) => {
-                self.settings.disable_interactivity = v;
+                self.settings.winget.disable_interactivity = v;
                 self.save_settings()
             }
             Message::SetInstallLocation(v) => {
-                self.settings.install_location = v;
+                self.settings.winget.install_location = v;
                 Task::none()
             }
+            Message::ToggleCatalogSource(i, v) => {
+                if let Some(source) = self.settings.catalog.sources.get_mut(i) {
+                    source.enabled = v;
+                }
+                self.save_settings()
+            }
+            Message::MoveCatalogSource(from, to) => {
+                let sources = &mut self.settings.catalog.sources;
+                if from < sources.len() && to < sources.len() {
+                    sources.swap(from, to);
+                }
+                self.save_settings()
+            }
+            Message::RemoveCatalogSource(i) => {
+                if i < self.settings.catalog.sources.len() {
+                    self.settings.catalog.sources.remove(i);
+                }
+                self.save_settings()
+            }
+            Message::NewCatalogSourceChanged(v) => {
+                self.new_catalog_source = v;
+                Task::none()
+            }
+            Message::NewCatalogSourceKeyChanged(v) => {
+                self.new_catalog_source_key = v;
+                Task::none()
+            }
+            Message::AddCatalogSource => self.handle_add_catalog_source(),
             Message::KeyIgnored | Message::Noop(()) => Task::none(),
         }
     }
@@ -465,19 +825,135 @@ impl App {
 
     // ── Navigation & lifecycle ───────────────────────────────────
 
+    /// The cached catalog (if any) is loaded; show it, then revalidate it.
     fn handle_catalog_fetched(
         &mut self,
-        result: Result<(Vec<Package>, CatalogSource), String>,
+        result: Result<(Catalog, CatalogSource), CatalogError>,
+    ) -> Task<Message> {
+        let rescan = match result {
+            Ok((catalog, source)) => self.set_catalog(catalog, source),
+            Err(CatalogError::Rejected(warning)) => {
+                self.catalog.warnings.push(warning);
+                Task::none()
+            }
+            Err(CatalogError::Unavailable(_)) => Task::none(),
+        };
+        Task::batch([rescan, self.start_catalog_refresh(false)])
+    }
+
+    fn start_catalog_refresh(&mut self, force: bool) -> Task<Message> {
+        if self.catalog_refreshing {
+            return Task::none();
+        }
+        self.catalog_refreshing = true;
+        Task::perform(
+            catalog::refresh_catalog(self.settings.catalog.enabled_sources(), self.dry_run, force),
+            Message::CatalogRefreshed,
+        )
+    }
+
+    fn handle_catalog_refreshed(
+        &mut self,
+        result: Result<CatalogRefresh, CatalogError>,
     ) -> Task<Message> {
-        if let Ok((packages, source)) = result {
-            self.catalog = packages;
-            self.catalog_source = source;
-            let valid_ids: HashSet<&str> = self.catalog.iter().map(|p| p.id.as_str()).collect();
-            self.selected.retain(|id| valid_ids.contains(id.as_str()));
+        self.catalog_refreshing = false;
+        self.catalog_unreachable = None;
+        match result {
+            Ok(CatalogRefresh::Fresh) => {}
+            Ok(CatalogRefresh::NotModified) => {
+                self.catalog_status = Some(CatalogStatus::Success("Catalog is up to date".into()));
+            }
+            Ok(CatalogRefresh::Updated(catalog)) => {
+                self.catalog_status = Some(CatalogStatus::Success("Catalog updated".into()));
+                return self.set_catalog(catalog, CatalogSource::Remote);
+            }
+            Err(CatalogError::Rejected(warning)) => {
+                self.catalog.warnings.push(warning.clone());
+                self.catalog_status = Some(CatalogStatus::Error(warning));
+            }
+            Err(CatalogError::Unavailable(reason)) => {
+                self.catalog_status =
+                    Some(CatalogStatus::Error(format!("Refresh failed: {reason}")));
+                self.catalog_unreachable = Some(reason);
+            }
         }
         Task::none()
     }
 
+    fn handle_add_catalog_source(&mut self) -> Task<Message> {
+        let location = self.new_catalog_source.trim().to_string();
+        if let Err(e) = catalog::check_source_location(&location) {
+            self.catalog_status = Some(CatalogStatus::Error(format!("Can't add source: {e}")));
+            return Task::none();
+        }
+        let key = self.new_catalog_source_key.trim().to_string();
+        if !key.is_empty()
+            && let Err(e) = catalog::check_public_key(&key)
+        {
+            self.catalog_status = Some(CatalogStatus::Error(format!("Can't add source: {e}")));
+            return Task::none();
+        }
+        let sources = &mut self.settings.catalog.sources;
+        if sources.iter().any(|s| s.location == location) {
+            self.catalog_status = Some(CatalogStatus::Error(
+                "Can't add source: already in the list".into(),
+            ));
+            return Task::none();
+        }
+        sources.push(settings::CatalogSourceEntry {
+            location,
+            enabled: true,
+            public_key: (!key.is_empty()).then_some(key),
+        });
+        self.new_catalog_source.clear();
+        self.new_catalog_source_key.clear();
+        self.catalog_status = None;
+        self.save_settings()
+    }
+
+    /// Swap in a new catalog, dropping selections for packages it no longer
+    /// has. Packages added from winget search carry over. Backends and
+    /// detect rules the installed scans haven't covered are scanned now.
+    fn set_catalog(&mut self, catalog: Catalog, source: CatalogSource) -> Task<Message> {
+        let old = std::mem::replace(&mut self.catalog, catalog);
+        for pkg in old.packages.into_iter().filter(Package::is_ad_hoc) {
+            self.catalog.add_ad_hoc(pkg);
+        }
+        self.catalog_source = source;
+        let valid_ids: HashSet<&str> = self
+            .catalog
+            .packages
+            .iter()
+            .map(|p| p.id.as_str())
+            .collect();
+        self.selected.retain(|id| valid_ids.contains(id.as_str()));
+
+        let backends: Vec<Backend> = self
+            .catalog
+            .backends()
+            .into_iter()
+            .filter(|b| self.scanned_backends.insert(*b))
+            .collect();
+        let detect_rules: Vec<(String, Detect)> = self
+            .catalog
+            .detect_rules()
+            .into_iter()
+            .filter(|(key, rule)| self.scanned_detect_rules.get(key) != Some(rule))
+            .collect();
+        if backends.is_empty() && detect_rules.is_empty() {
+            return Task::none();
+        }
+        for (key, rule) in &detect_rules {
+            // A changed rule decides afresh whether the package is installed
+            self.installed.remove(key);
+            self.scanned_detect_rules.insert(key.clone(), rule.clone());
+        }
+        Task::perform(
+            upgrade::scan_catalog_installed(self.dry_run, backends, detect_rules),
+            Message::CatalogInstalledScanned,
+        )
+    }
+
     fn handle_installed_scan_progress(
         &mut self,
         event: upgrade::InstalledScanProgress,
@@ -486,7 +962,7 @@ impl App {
             upgrade::InstalledScanProgress::Activity { .. } => {}
             upgrade::InstalledScanProgress::Completed { packages } => {
                 for pkg in packages {
-                    self.installed.insert(pkg.winget_id, pkg.version);
+                    self.installed.insert(pkg.key.clone(), pkg);
                 }
                 self.installed_scan_done = true;
                 self._installed_scan_handle = None;
@@ -499,22 +975,105 @@ impl App {
         Task::none()
     }
 
-    fn handle_profile_selected(&mut self, profile: Profile) -> Task<Message> {
-        self.selected_profile = Some(profile);
-        let mut selection = catalog::default_selection(&self.catalog, profile);
+    fn handle_profile_selected(&mut self, slug: String) -> Task<Message> {
+        let mut selection = catalog::default_selection(&self.catalog, &slug);
+        self.selected_profile = Some(slug);
         if self.installed_scan_done {
-            for pkg in &self.catalog {
+            for pkg in &self.catalog.packages {
                 if self.is_installed(pkg) {
                     selection.remove(&pkg.id);
                 }
             }
         }
         self.selected = selection;
+        self.select_dependencies();
         self.search.clear();
+        self.active_tags.clear();
+        self.uninstall_mode = false;
+        self.uninstall_selected.clear();
         self.screen = Screen::PackageSelect;
         Task::none()
     }
 
+    /// Open the details panel for a package, or close it if it's already
+    /// open. Winget and Store packages run `winget show` once per session.
+    fn handle_show_details(&mut self, id: String) -> Task<Message> {
+        if self.details_open.as_ref() == Some(&id) {
+            self.details_open = None;
+            return Task::none();
+        }
+        self.details_open = Some(id.clone());
+        if self.winget_details.contains_key(&id) {
+            return Task::none();
+        }
+        let Some(args) = self
+            .catalog
+            .packages
+            .iter()
+            .find(|p| p.id == id)
+            .and_then(|p| p.installer.as_ref())
+            .and_then(details::show_args)
+        else {
+            return Task::none();
+        };
+        self.winget_details.insert(id.clone(), None);
+        Task::perform(details::fetch(args, self.dry_run), move |result| {
+            Message::DetailsFetched(id, result)
+        })
+    }
+
+    fn handle_search_winget(&mut self) -> Task<Message> {
+        let query = self.search.trim().to_string();
+        if query.is_empty() || self.winget_search.running {
+            return Task::none();
+        }
+        self.winget_search = WingetSearchState {
+            query: query.clone(),
+            running: true,
+            ..Default::default()
+        };
+        Task::perform(
+            upgrade::search_winget(query.clone(), self.dry_run),
+            move |result| Message::WingetSearchCompleted(query, result),
+        )
+    }
+
+    fn handle_winget_search_completed(
+        &mut self,
+        query: String,
+        result: Result<Vec<upgrade::SearchResult>, String>,
+    ) -> Task<Message> {
+        // Results for a search that was reset in the meantime are dropped
+        if query != self.winget_search.query {
+            return Task::none();
+        }
+        self.winget_search.running = false;
+        match result {
+            Ok(results) => self.winget_search.results = results,
+            Err(e) => self.winget_search.error = Some(e),
+        }
+        Task::none()
+    }
+
+    /// Tick or untick a winget search result. One that isn't in the catalog
+    /// yet is added as an ad-hoc package first.
+    fn handle_toggle_winget_result(&mut self, index: usize) -> Task<Message> {
+        let Some(result) = self.winget_search.results.get(index) else {
+            return Task::none();
+        };
+        let id = match self.search_result_package(result) {
+            Some(pkg) => pkg.id.clone(),
+            None => {
+                let pkg = Package::ad_hoc(&result.id, &result.name, &result.source);
+                let id = pkg.id.clone();
+                self.catalog.add_ad_hoc(pkg);
+                id
+            }
+        };
+        self.toggle_package(id);
+        Task::none()
+    }
+
     fn handle_go_back(&mut self) -> Task<Message> {
         match self.screen {
             Screen::Review => {
@@ -533,6 +1092,9 @@ impl App {
             }
             _ => {
                 self.search.clear();
+                self.active_tags.clear();
+                self.details_open = None;
+                self.winget_search = WingetSearchState::default();
                 self.screen = Screen::ProfileSelect;
             }
         }
@@ -543,12 +1105,25 @@ impl App {
         self.selected_profile = None;
         self.selected.clear();
         self.search.clear();
+        self.active_tags.clear();
         self.install_queue.clear();
         self.install = ProgressState::default();
         self.screen = Screen::ProfileSelect;
         Task::none()
     }
 
+    fn handle_finish_uninstall_and_reset(&mut self) -> Task<Message> {
+        self.selected_profile = None;
+        self.search.clear();
+        self.active_tags.clear();
+        self.uninstall_mode = false;
+        self.uninstall_selected.clear();
+        self.uninstall_queue.clear();
+        self.uninstall = ProgressState::default();
+        self.screen = Screen::ProfileSelect;
+        Task::none()
+    }
+
     fn handle_finish_update_and_reset(&mut self) -> Task<Message> {
         self.update_scan = UpdateScanState::default();
         self.upgrade_queue.clear();
@@ -562,19 +1137,21 @@ impl App {
     fn handle_start_install(&mut self) -> Task<Message> {
         let queue: Vec<Package> = self
             .catalog
+            .packages
             .iter()
-            .filter(|p| self.selected.contains(&p.id))
+            .filter(|p| self.selected.contains(&p.id) && self.is_compatible(p))
             .cloned()
             .collect();
+        let queue = install::order_by_dependencies(queue);
 
         self.install.start(queue.len());
         self.install_queue = queue.clone();
         self.screen = Screen::Installing;
 
         let dry = self.dry_run;
-        let extra = self.settings.install_args();
+        let settings = self.settings.winget.clone();
         let (task, handle) = Task::run(
-            install::install_all(queue, dry, extra),
+            install::install_all(queue, dry, settings),
             Message::InstallProgress,
         )
         .abortable();
@@ -605,7 +1182,7 @@ impl App {
         self.screen = Screen::UpdateScanning;
 
         let dry = self.dry_run;
-        let include_unknown = self.settings.include_unknown;
+        let include_unknown = self.settings.winget.include_unknown;
         let (task, handle) = Task::run(
             upgrade::scan_upgrades(dry, include_unknown),
             Message::UpdateScanProgress,
@@ -636,15 +1213,19 @@ impl App {
                         .drain(..self.update_scan.log.len() - LOG_CAP);
                 }
             }
-            upgrade::ScanProgress::Completed { packages } => {
+            upgrade::ScanProgress::Completed { mut packages } => {
                 self.update_scan.done = true;
                 self.update_scan.live_line.clear();
                 self.update_scan._handle = None;
+                self.flag_pinned_upgrades(&mut packages);
                 if packages.is_empty() {
                     self.update_scan.packages = packages;
                 } else {
-                    self.update_scan.selected =
-                        packages.iter().map(|p| p.winget_id.clone()).collect();
+                    self.update_scan.selected = packages
+                        .iter()
+                        .filter(|p| p.pin.is_none())
+                        .map(|p| p.winget_id.clone())
+                        .collect();
                     self.update_scan.packages = packages;
                     self.screen = Screen::UpdateSelect;
                 }
@@ -659,6 +1240,25 @@ impl App {
         Task::none()
     }
 
+    /// The catalog package a winget upgrade is for, if any.
+    fn catalog_package_for(&self, upgrade: &UpgradeablePackage) -> Option<&Package> {
+        let id = upgrade.winget_id.to_lowercase();
+        self.catalog
+            .packages
+            .iter()
+            .find(|p| p.installed_key.as_deref() == Some(id.as_str()))
+    }
+
+    /// Mark upgrades whose available version breaks the catalog's version pin.
+    fn flag_pinned_upgrades(&self, packages: &mut [UpgradeablePackage]) {
+        for upgrade in packages {
+            upgrade.pin = self
+                .catalog_package_for(upgrade)
+                .and_then(|p| p.version.clone())
+                .filter(|pin| !pin.matches(&upgrade.available_version));
+        }
+    }
+
     // ── Upgrade flow ─────────────────────────────────────────────
 
     fn handle_start_upgrade(&mut self) -> Task<Message> {
@@ -675,9 +1275,16 @@ impl App {
         self.screen = Screen::Updating;
 
         let dry = self.dry_run;
-        let extra = self.settings.install_args();
+        // Catalog packages upgrade with the same winget overrides they install with
+        let args = queue
+            .iter()
+            .map(|upgrade| match self.catalog_package_for(upgrade) {
+                Some(pkg) => self.settings.winget.install_args_for(&pkg.winget),
+                None => self.settings.winget.install_args(),
+            })
+            .collect();
         let (task, handle) = Task::run(
-            upgrade::upgrade_all(queue, dry, extra),
+            upgrade::upgrade_all(queue, dry, args),
             Message::UpgradeProgress,
         )
         .abortable();
@@ -700,45 +1307,108 @@ impl App {
         Task::none()
     }
 
+    // ── Uninstall flow ───────────────────────────────────────────
+
+    fn handle_start_uninstall(&mut self) -> Task<Message> {
+        let queue = self.removals();
+
+        self.uninstall.start(queue.len());
+        self.uninstall_queue = queue.clone();
+        self.screen = Screen::Uninstalling;
+
+        let (task, handle) = Task::run(
+            uninstall::uninstall_all(queue, self.dry_run),
+            Message::UninstallProgress,
+        )
+        .abortable();
+
+        self.uninstall._handle = Some(handle.abort_on_drop());
+        task
+    }
+
+    fn handle_cancel_uninstall(&mut self) -> Task<Message> {
+        self.uninstall.cancel("Uninstall");
+        Task::none()
+    }
+
+    fn handle_uninstall_progress(&mut self, event: install::InstallProgress) -> Task<Message> {
+        if let install::InstallProgress::Succeeded { index } = event
+            && let Some(removal) = self.uninstall_queue.get(index)
+        {
+            self.installed.remove(&removal.key);
+            self.uninstall_selected.remove(&removal.key);
+        }
+        let queue = &self.uninstall_queue;
+        self.uninstall.handle_event(&event, |i| {
+            let name = queue.get(i).map(|r| r.name.as_str()).unwrap_or("...");
+            format!("Uninstalling {name}")
+        });
+        Task::none()
+    }
+
     // ── Selection ────────────────────────────────────────────────
 
     fn handle_toggle_category(&mut self, cat: String) -> Task<Message> {
+        if self.uninstall_mode {
+            let keys: Vec<String> = self
+                .catalog
+                .packages
+                .iter()
+                .filter(|p| p.category == cat && self.is_removable(p))
+                .filter_map(|p| p.installed_key.clone())
+                .collect();
+            toggle_set(&mut self.uninstall_selected, keys);
+            return Task::none();
+        }
         let cat_ids: Vec<String> = self
             .catalog
+            .packages
             .iter()
-            .filter(|p| p.category == cat)
+            .filter(|p| p.category == cat && self.is_compatible(p))
             .map(|p| p.id.clone())
             .collect();
         toggle_set(&mut self.selected, cat_ids);
+        self.select_dependencies();
         Task::none()
     }
 
     fn handle_select_all(&mut self) -> Task<Message> {
-        let search_lower = self.search.to_lowercase();
+        let query = search::Query::new(&self.search);
         match self.screen {
+            Screen::PackageSelect if self.uninstall_mode => {
+                let mut visible_keys: Vec<String> = self
+                    .catalog
+                    .packages
+                    .iter()
+                    .filter(|p| self.is_removable(p) && self.package_match(p, &query).is_some())
+                    .filter_map(|p| p.installed_key.clone())
+                    .collect();
+                visible_keys.extend(
+                    self.other_installed()
+                        .into_iter()
+                        .filter(|p| query.is_empty() || p.search_match(&query).is_some())
+                        .map(|p| p.key.clone()),
+                );
+                toggle_set(&mut self.uninstall_selected, visible_keys);
+            }
             Screen::PackageSelect => {
                 let visible_ids: Vec<String> = self
                     .catalog
+                    .packages
                     .iter()
-                    .filter(|p| {
-                        search_lower.is_empty()
-                            || p.name_lower.contains(&search_lower)
-                            || p.desc_lower.contains(&search_lower)
-                    })
+                    .filter(|p| self.is_compatible(p) && self.package_match(p, &query).is_some())
                     .map(|p| p.id.clone())
                     .collect();
                 toggle_set(&mut self.selected, visible_ids);
+                self.select_dependencies();
             }
             Screen::UpdateSelect => {
                 let visible_ids: Vec<String> = self
                     .update_scan
                     .packages
                     .iter()
-                    .filter(|p| {
-                        search_lower.is_empty()
-                            || p.name_lower.contains(&search_lower)
-                            || p.winget_id_lower.contains(&search_lower)
-                    })
+                    .filter(|p| p.pin.is_none())
+                    .filter(|p| query.is_empty() || p.search_match(&query).is_some())
                     .map(|p| p.winget_id.clone())
                     .collect();
                 toggle_set(&mut self.update_scan.selected, visible_ids);
@@ -772,7 +1442,8 @@ impl App {
     }
 
     fn handle_import_selection(&mut self) -> Task<Message> {
-        let valid_ids: HashSet<String> = self.catalog.iter().map(|p| p.id.clone()).collect();
+        let valid_ids: HashSet<String> =
+            self.catalog.packages.iter().map(|p| p.id.clone()).collect();
         Task::perform(
             catalog::import_selection(valid_ids),
             Message::ImportCompleted,
@@ -786,7 +1457,8 @@ impl App {
         match result {
             Ok(ids) => {
                 let count = ids.len();
-                self.selected = ids;
+                self.selected = ids.into_iter().map(|id| self.import_id(id)).collect();
+                self.select_dependencies();
                 self.selection_status = Some(format!("{count} packages imported"));
             }
             Err(msg) if msg.is_empty() => return Task::none(),
@@ -797,9 +1469,29 @@ impl App {
         delayed_clear(Message::ClearSelectionStatus)
     }
 
+    /// The catalog ID for an imported one. Packages from winget search, like
+    /// `winget:Git.Git`, are added again, or mapped to the catalog package
+    /// with the same winget ID.
+    fn import_id(&mut self, id: String) -> String {
+        let Some(pkg) = Package::from_ad_hoc_id(&id) else {
+            return id;
+        };
+        if let Some(existing) = self
+            .catalog
+            .packages
+            .iter()
+            .find(|p| p.installed_key == pkg.installed_key)
+        {
+            return existing.id.clone();
+        }
+        self.catalog.add_ad_hoc(pkg);
+        id
+    }
+
     fn handle_copy_log(&mut self, lines: Vec<String>) -> Task<Message> {
         let state = match self.screen {
             Screen::Updating => &self.upgrade,
+            Screen::Uninstalling => &self.uninstall,
             _ => &self.install,
         };
         let (done, failed, cancelled) = state.status_counts();
@@ -811,6 +1503,7 @@ impl App {
 
         match self.screen {
             Screen::Updating => self.upgrade.copy_status = true,
+            Screen::Uninstalling => self.uninstall.copy_status = true,
             _ => self.install.copy_status = true,
         }
 
@@ -824,10 +1517,17 @@ impl App {
 
     fn handle_key_confirm(&mut self) -> Task<Message> {
         match self.screen {
+            Screen::PackageSelect if self.uninstall_mode => {
+                if !self.uninstall_selected.is_empty() {
+                    self.screen = Screen::Review;
+                }
+                Task::none()
+            }
             Screen::PackageSelect if !self.selected.is_empty() => {
                 self.screen = Screen::Review;
                 Task::none()
             }
+            Screen::Review if self.uninstall_mode => self.handle_start_uninstall(),
             Screen::Review => self.handle_start_install(),
             Screen::Installing if self.install.done => self.handle_finish_and_reset(),
             Screen::UpdateScanning if self.update_scan.done => self.handle_go_back(),
@@ -835,18 +1535,24 @@ impl App {
                 self.handle_start_upgrade()
             }
             Screen::Updating if self.upgrade.done => self.handle_finish_update_and_reset(),
+            Screen::Uninstalling if self.uninstall.done => self.handle_finish_uninstall_and_reset(),
             _ => Task::none(),
         }
     }
 
     fn handle_key_escape(&mut self) -> Task<Message> {
         match self.screen {
+            Screen::PackageSelect if self.details_open.is_some() => {
+                self.details_open = None;
+                Task::none()
+            }
             Screen::PackageSelect | Screen::Review | Screen::UpdateSelect | Screen::Settings => {
                 self.handle_go_back()
             }
             Screen::Installing if !self.install.done => self.handle_cancel_install(),
             Screen::UpdateScanning if !self.update_scan.done => self.handle_cancel_update_scan(),
             Screen::Updating if !self.upgrade.done => self.handle_cancel_upgrade(),
+            Screen::Uninstalling if !self.uninstall.done => self.handle_cancel_uninstall(),
             _ => Task::none(),
         }
     }
@@ -860,6 +1566,7 @@ impl App {
             Screen::UpdateScanning => self.view_update_scanning(),
             Screen::UpdateSelect => self.view_update_select(),
             Screen::Updating => self.view_updating(),
+            Screen::Uninstalling => self.view_uninstalling(),
             Screen::Settings => self.view_settings(),
         }
     }
@@ -0,0 +1,302 @@
+//! Post-install steps: commands, scripts and settings applied after a
+//! package installs, each reported as a sub-step in the progress screen.
+//!
+//! `post_install` is either a single command line for `cmd /C`, whose
+//! failure is only logged as a warning, or a list of typed steps:
+//!
+//! ```toml
+//! post_install = [
+//!     { type = "cmd", command = "git config --system core.longpaths true" },
+//!     { type = "set-env", name = "EDITOR", value = "code --wait", run_as = "user" },
+//! ]
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use iced::futures::SinkExt as _;
+use tokio::process::Command;
+
+pub use crate::catalog_types::{Action, EnvScope, PostInstall, RunAs, Step};
+use crate::install::{self, InstallProgress, Sender, StepStatus};
+use crate::installer;
+use crate::system;
+
+/// Used when a step doesn't set `timeout`.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(600);
+/// How often to check whether a step running as the user has finished.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+impl PostInstall {
+    pub fn steps(&self) -> Vec<Step> {
+        match self {
+            Self::Command(command) => vec![Step {
+                action: Action::Cmd {
+                    command: command.clone(),
+                },
+                label: None,
+                run_as: RunAs::Elevated,
+                continue_on_error: true,
+                timeout: None,
+                success_codes: Vec::new(),
+            }],
+            Self::Steps(steps) => steps.clone(),
+        }
+    }
+
+    /// Whether a step has a type this version doesn't know.
+    pub fn is_unsupported(&self) -> bool {
+        match self {
+            Self::Command(_) => false,
+            Self::Steps(steps) => steps.iter().any(|s| s.action == Action::Unsupported),
+        }
+    }
+}
+
+impl Step {
+    pub fn label(&self) -> String {
+        if let Some(ref label) = self.label {
+            return label.clone();
+        }
+        match self.action {
+            Action::Cmd { ref command } => command.clone(),
+            Action::Powershell { ref script } => {
+                format!("PowerShell: {}", script.lines().next().unwrap_or_default())
+            }
+            Action::SetEnv { ref name, .. } => format!("Set {name}"),
+            Action::CopyFile { ref from, ref to } => format!("Copy {from} to {to}"),
+            Action::Unsupported => "Unsupported step".into(),
+        }
+    }
+
+    /// What a dry run would do.
+    pub fn describe(&self) -> String {
+        let action = match (&self.action, self.action.command()) {
+            (Action::CopyFile { from, to }, _) => format!("copy {from} to {to}"),
+            (_, Some((program, args))) => format!("run: {}", installer::describe(&program, &args)),
+            (_, None) => String::new(),
+        };
+        match self.run_as {
+            RunAs::Elevated => action,
+            RunAs::User => format!("{action} (as the signed-in user)"),
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TIMEOUT)
+    }
+
+    fn is_success(&self, code: i32) -> bool {
+        if self.success_codes.is_empty() {
+            code == 0
+        } else {
+            self.success_codes.contains(&code)
+        }
+    }
+}
+
+impl Action {
+    /// Program and arguments; `None` for copy-file, which runs in-process,
+    /// and for unsupported steps.
+    fn command(&self) -> Option<(String, Vec<String>)> {
+        let owned = |args: &[&str]| args.iter().map(|a| a.to_string()).collect();
+        match self {
+            Self::Cmd { command } => Some(("cmd".into(), owned(&["/C", command]))),
+            Self::Powershell { script } => Some((
+                "powershell".into(),
+                owned(&[
+                    "-NoProfile",
+                    "-NonInteractive",
+                    "-ExecutionPolicy",
+                    "Bypass",
+                    "-Command",
+                    script,
+                ]),
+            )),
+            Self::SetEnv { name, value, scope } => {
+                let mut args: Vec<String> = owned(&[name, &system::expand_env_vars(value)]);
+                if *scope == EnvScope::Machine {
+                    args.push("/M".into());
+                }
+                Some(("setx".into(), args))
+            }
+            Self::CopyFile { .. } | Self::Unsupported => None,
+        }
+    }
+}
+
+/// Run the steps in order as sub-steps of package `index`. Stops at the
+/// first failing step that doesn't `continue_on_error`.
+pub async fn run(post: &PostInstall, index: usize, sender: &mut Sender) -> Result<(), String> {
+    let steps = post.steps();
+    let labels = steps.iter().map(Step::label).collect();
+    let _ = sender.send(InstallProgress::Steps { index, labels }).await;
+
+    for (n, step) in steps.iter().enumerate() {
+        let position = format!("{}/{}", n + 1, steps.len());
+        set_status(sender, index, n, StepStatus::Running).await;
+        log(
+            sender,
+            index,
+            format!("Post-install step {position}: {}", step.label()),
+        )
+        .await;
+
+        let result = tokio::time::timeout(step.timeout(), run_step(step, index, n, sender))
+            .await
+            .unwrap_or_else(|_| Err(format!("Timed out after {}s", step.timeout().as_secs())));
+
+        match result {
+            Ok(()) => set_status(sender, index, n, StepStatus::Done).await,
+            Err(e) if step.continue_on_error => {
+                log(sender, index, format!("Post-install warning: {e}")).await;
+                set_status(sender, index, n, StepStatus::Warning(e)).await;
+            }
+            Err(e) => {
+                set_status(sender, index, n, StepStatus::Failed(e.clone())).await;
+                return Err(format!(
+                    "Post-install step {position} ({}) failed: {e}",
+                    step.label()
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run_step(step: &Step, index: usize, n: usize, sender: &mut Sender) -> Result<(), String> {
+    match step.action {
+        Action::CopyFile { ref from, ref to } => return copy_file(from, to).await,
+        Action::Unsupported => return Err("Not supported by this version of Provision".into()),
+        _ => {}
+    }
+    let Some((program, args)) = step.action.command() else {
+        return Ok(());
+    };
+
+    let code = match step.run_as {
+        RunAs::Elevated => {
+            let mut command = Command::new(&program);
+            // Dropped on timeout: stop the process rather than leave it running
+            command.args(&args).kill_on_drop(true);
+            command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+            install::run_process(command, index, sender).await?
+        }
+        RunAs::User => {
+            log(
+                sender,
+                index,
+                "Running as the signed-in user in a separate window".into(),
+            )
+            .await;
+            run_as_user(step, &program, &args, index, n).await?
+        }
+    };
+
+    if step.is_success(code) {
+        Ok(())
+    } else {
+        Err(format!("Exit code: {code}"))
+    }
+}
+
+/// Run a step de-elevated through `runas /trustlevel`, which doesn't wait
+/// or report an exit code: the step goes into a batch file that writes its
+/// exit code to a marker file, and we wait for the marker.
+async fn run_as_user(
+    step: &Step,
+    program: &str,
+    args: &[String],
+    index: usize,
+    n: usize,
+) -> Result<i32, String> {
+    let dir = std::env::temp_dir().join("provision");
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Create {}: {e}", dir.display()))?;
+    let stem = dir.join(format!("post-install-{index}-{n}"));
+    let script = stem.with_extension("cmd");
+    let marker = stem.with_extension("exit");
+    let _ = tokio::fs::remove_file(&marker).await;
+
+    let line = match step.action {
+        Action::Cmd { ref command } => command.clone(),
+        // A script file avoids quoting the script inside a batch line
+        Action::Powershell { script: ref body } => {
+            let ps1 = stem.with_extension("ps1");
+            // -File reports 0 unless the script exits with a code itself
+            write(&ps1, &format!("{body}\r\nexit $LASTEXITCODE\r\n")).await?;
+            format!(
+                "powershell -NoProfile -NonInteractive -ExecutionPolicy Bypass -File \"{}\"",
+                ps1.display()
+            )
+        }
+        _ => installer::describe(program, args),
+    };
+    let batch = format!(
+        "@echo off\r\n{line}\r\n>\"{}\" echo %ERRORLEVEL%\r\n",
+        marker.display()
+    );
+    write(&script, &batch).await?;
+
+    let mut runas = Command::new("runas");
+    runas.args([
+        "/trustlevel:0x20000".to_string(),
+        format!("\"{}\"", script.display()),
+    ]);
+    runas.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let status = runas
+        .status()
+        .await
+        .map_err(|e| format!("Failed to spawn: {e}"))?;
+    if !status.success() {
+        return Err("Could not start as the signed-in user".into());
+    }
+
+    loop {
+        // The marker can be seen before echo has written to it
+        if let Ok(text) = tokio::fs::read_to_string(&marker).await
+            && let Ok(code) = text.trim().parse()
+        {
+            let _ = tokio::fs::remove_file(&marker).await;
+            return Ok(code);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn copy_file(from: &str, to: &str) -> Result<(), String> {
+    let from = PathBuf::from(system::expand_env_vars(from));
+    let to = PathBuf::from(system::expand_env_vars(to));
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Create {}: {e}", parent.display()))?;
+    }
+    tokio::fs::copy(&from, &to)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Copy {} to {}: {e}", from.display(), to.display()))
+}
+
+async fn write(path: &Path, contents: &str) -> Result<(), String> {
+    tokio::fs::write(path, contents)
+        .await
+        .map_err(|e| format!("Write {}: {e}", path.display()))
+}
+
+async fn set_status(sender: &mut Sender, index: usize, step: usize, status: StepStatus) {
+    let _ = sender
+        .send(InstallProgress::Step {
+            index,
+            step,
+            status,
+        })
+        .await;
+}
+
+async fn log(sender: &mut Sender, index: usize, line: String) {
+    let _ = sender.send(InstallProgress::Log { index, line }).await;
+}
@@ -1,44 +1,22 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Profile {
-    Laptop,
-    Desktop,
-    Manual,
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// A provisioning profile, declared in the catalog's `[[profiles]]` table.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct Profile {
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    /// Lucide icon name, e.g. `"laptop"`.
+    pub icon: String,
+    /// Slug of another profile whose packages this one also pre-selects.
+    pub extends: Option<String>,
 }
 
 impl Profile {
-    pub const ALL: [Profile; 3] = [Profile::Laptop, Profile::Desktop, Profile::Manual];
-
-    pub fn title(self) -> &'static str {
-        match self {
-            Profile::Laptop => "Laptop",
-            Profile::Desktop => "Desktop",
-            Profile::Manual => "Manual",
-        }
-    }
-
-    pub fn description(self) -> &'static str {
-        match self {
-            Profile::Laptop => "Portable essentials",
-            Profile::Desktop => "Full setup",
-            Profile::Manual => "Start from scratch",
-        }
-    }
-
-    pub fn slug(self) -> &'static str {
-        match self {
-            Profile::Laptop => "laptop",
-            Profile::Desktop => "desktop",
-            Profile::Manual => "manual",
-        }
-    }
-
-    /// Lucide icon for this profile.
-    pub fn icon(self) -> char {
+    /// Lucide icon for this profile, falling back to a generic package icon.
+    pub fn icon(&self) -> char {
         use lucide_icons::Icon;
-        match self {
-            Profile::Laptop => char::from(Icon::Laptop),
-            Profile::Desktop => char::from(Icon::Monitor),
-            Profile::Manual => char::from(Icon::Settings),
-        }
+        char::from(Icon::try_from(self.icon.as_str()).unwrap_or(Icon::Package))
     }
 }
@@ -0,0 +1,335 @@
+//! Ranked fuzzy matching for the package and update search boxes.
+//!
+//! Each whitespace-separated query term is aligned against the item's fields
+//! (name, winget ID, tags, description) with an fzf-style scoring pass that
+//! rewards matches at word boundaries and runs of consecutive characters, so
+//! `vscode` finds "Visual Studio Code" and `Microsoft.VisualStudioCode`.
+//! Terms that don't align are retried against whole words with a small edit
+//! distance to tolerate typos.
+
+use std::ops::Range;
+
+const SCORE_MATCH: i32 = 16;
+const BONUS_BOUNDARY: i32 = 24;
+/// Extra bonus when the match starts the field.
+const BONUS_FIRST_CHAR: i32 = 8;
+const BONUS_CONSECUTIVE: i32 = 16;
+const PENALTY_GAP_START: i32 = 3;
+const PENALTY_GAP_EXTEND: i32 = 1;
+
+const WEIGHT_NAME: i32 = 4;
+const WEIGHT_ID: i32 = 2;
+const WEIGHT_TAG: i32 = 2;
+const WEIGHT_DESCRIPTION: i32 = 1;
+
+/// A parsed search query: lowercased terms that must all match.
+#[derive(Debug, Default)]
+pub struct Query {
+    terms: Vec<Vec<char>>,
+}
+
+impl Query {
+    pub fn new(text: &str) -> Self {
+        Self {
+            terms: text
+                .split_whitespace()
+                .map(|term| term.chars().map(lower).collect())
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+/// The searchable text of one item.
+pub struct Item<'a> {
+    pub name: &'a str,
+    pub description: &'a str,
+    pub id: Option<&'a str>,
+    pub tags: &'a [String],
+}
+
+/// A successful match: its relevance and the byte ranges to highlight.
+#[derive(Debug, Clone, Default)]
+pub struct Match {
+    pub score: i32,
+    pub name: Vec<Range<usize>>,
+    pub description: Vec<Range<usize>>,
+}
+
+/// Match every query term against an item. Returns `None` if any term
+/// matches none of the fields. An empty query matches with score zero.
+pub fn match_item(query: &Query, item: &Item<'_>) -> Option<Match> {
+    let name = Haystack::new(item.name);
+    let description = Haystack::new(item.description);
+    let id = item.id.map(Haystack::new);
+    let tags: Vec<Haystack> = item.tags.iter().map(|t| Haystack::new(t)).collect();
+
+    let mut result = Match::default();
+    let mut name_positions = Vec::new();
+    let mut description_positions = Vec::new();
+
+    for term in &query.terms {
+        let mut best = None::<i32>;
+        let mut consider = |score: i32| best = Some(best.map_or(score, |b| b.max(score)));
+
+        if let Some((score, positions)) = name.find(term) {
+            consider(score * WEIGHT_NAME);
+            name_positions.extend(positions);
+        }
+        if let Some((score, positions)) = description.find(term) {
+            consider(score * WEIGHT_DESCRIPTION);
+            description_positions.extend(positions);
+        }
+        if let Some((score, _)) = id.as_ref().and_then(|id| id.find(term)) {
+            consider(score * WEIGHT_ID);
+        }
+        for tag in &tags {
+            if let Some((score, _)) = tag.find(term) {
+                consider(score * WEIGHT_TAG);
+            }
+        }
+
+        result.score += best?;
+    }
+
+    result.name = name.ranges(name_positions);
+    result.description = description.ranges(description_positions);
+    Some(result)
+}
+
+fn lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// A field prepared for matching: lowercased chars, their byte offsets in the
+/// original text, and which chars start a word.
+struct Haystack {
+    chars: Vec<char>,
+    offsets: Vec<usize>,
+    boundary: Vec<bool>,
+    len: usize,
+}
+
+impl Haystack {
+    fn new(text: &str) -> Self {
+        let mut chars = Vec::new();
+        let mut offsets = Vec::new();
+        let mut boundary = Vec::new();
+        let mut prev: Option<char> = None;
+
+        for (offset, c) in text.char_indices() {
+            let starts_word = match prev {
+                None => true,
+                Some(p) => {
+                    !p.is_alphanumeric()
+                        || (p.is_lowercase() && c.is_uppercase())
+                        || (p.is_alphabetic() && c.is_numeric())
+                }
+            };
+            chars.push(lower(c));
+            offsets.push(offset);
+            boundary.push(starts_word && c.is_alphanumeric());
+            prev = Some(c);
+        }
+
+        Self {
+            chars,
+            offsets,
+            boundary,
+            len: text.len(),
+        }
+    }
+
+    fn bonus(&self, j: usize) -> i32 {
+        match (self.boundary[j], j == 0) {
+            (true, true) => BONUS_BOUNDARY + BONUS_FIRST_CHAR,
+            (true, false) => BONUS_BOUNDARY,
+            _ => 0,
+        }
+    }
+
+    /// Best alignment of `term`, falling back to a typo-tolerant word match.
+    /// Returns the score and matched char positions.
+    fn find(&self, term: &[char]) -> Option<(i32, Vec<usize>)> {
+        self.align(term).or_else(|| self.find_typo(term))
+    }
+
+    /// Highest-scoring alignment of `term` as a subsequence. Accepted only if
+    /// it is a plain substring or runs from word start to word start (acronyms,
+    /// camelCase), which drops scattered hits.
+    fn align(&self, term: &[char]) -> Option<(i32, Vec<usize>)> {
+        const NONE: i32 = i32::MIN / 2;
+        let (n, m) = (term.len(), self.chars.len());
+        if n == 0 || n > m {
+            return None;
+        }
+
+        // score[i * m + j]: best score with term[i] matched at chars[j]
+        let mut score = vec![NONE; n * m];
+        let mut from = vec![0usize; n * m];
+
+        for (j, &c) in self.chars.iter().enumerate() {
+            if c == term[0] {
+                score[j] = SCORE_MATCH + self.bonus(j);
+            }
+        }
+
+        for i in 1..n {
+            let (done, rest) = score.split_at_mut(i * m);
+            let prev = &done[(i - 1) * m..];
+            let cur = &mut rest[..m];
+            let row = i * m;
+            // Best `prev[k] - gap penalty` over k <= j - 2, carried along the row.
+            let mut carry = NONE;
+            let mut carry_k = 0;
+            for j in 0..m {
+                if j >= 2 {
+                    let open = prev[j - 2] - PENALTY_GAP_START;
+                    if open >= carry - PENALTY_GAP_EXTEND {
+                        carry = open;
+                        carry_k = j - 2;
+                    } else {
+                        carry -= PENALTY_GAP_EXTEND;
+                    }
+                }
+                if self.chars[j] != term[i] {
+                    continue;
+                }
+
+                let mut best = NONE;
+                if j >= 1 && prev[j - 1] > NONE {
+                    best = prev[j - 1] + BONUS_CONSECUTIVE;
+                    from[row + j] = j - 1;
+                }
+                if carry > best {
+                    best = carry;
+                    from[row + j] = carry_k;
+                }
+                if best > NONE / 2 {
+                    cur[j] = best + SCORE_MATCH + self.bonus(j);
+                }
+            }
+        }
+
+        let last = (n - 1) * m;
+        let (end, &best) = score[last..last + m]
+            .iter()
+            .enumerate()
+            .max_by_key(|&(j, s)| (*s, std::cmp::Reverse(j)))?;
+        if best <= NONE / 2 {
+            return None;
+        }
+
+        let mut positions = vec![end; n];
+        for i in (1..n).rev() {
+            positions[i - 1] = from[i * m + positions[i]];
+        }
+
+        // A match starting mid-word must be a plain substring; otherwise every
+        // later char must start a word or directly follow the previous one
+        let starts_word = self.boundary[positions[0]];
+        let tight = positions
+            .windows(2)
+            .all(|pair| pair[0] + 1 == pair[1] || (starts_word && self.boundary[pair[1]]));
+        if tight {
+            Some((best, positions))
+        } else {
+            self.substring(term)
+        }
+    }
+
+    /// Best plain substring occurrence of `term`, preferring word starts.
+    fn substring(&self, term: &[char]) -> Option<(i32, Vec<usize>)> {
+        let run = (term.len() as i32 - 1) * BONUS_CONSECUTIVE;
+        self.chars
+            .windows(term.len())
+            .enumerate()
+            .filter(|(_, window)| *window == term)
+            .map(|(start, _)| {
+                let score = term.len() as i32 * SCORE_MATCH + run + self.bonus(start);
+                (score, (start..start + term.len()).collect())
+            })
+            .max_by_key(|(score, _)| *score)
+    }
+
+    /// Match `term` against whole words within a small edit distance:
+    /// one typo for terms of 4+ chars, two for 8+.
+    fn find_typo(&self, term: &[char]) -> Option<(i32, Vec<usize>)> {
+        let allowed = match term.len() {
+            0..4 => return None,
+            4..8 => 1,
+            _ => 2,
+        };
+
+        self.words()
+            .filter(|word| word.len().abs_diff(term.len()) <= allowed)
+            .filter_map(|word| {
+                let distance = edit_distance(term, &self.chars[word.clone()]);
+                (distance <= allowed).then(|| {
+                    let score = (term.len() - distance) as i32 * SCORE_MATCH / 2;
+                    (score, word.collect())
+                })
+            })
+            .max_by_key(|(score, _)| *score)
+    }
+
+    /// Char ranges of the words in this field (alphanumeric runs, split at
+    /// camelCase and letter/digit boundaries).
+    fn words(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        let starts: Vec<usize> = (0..self.chars.len())
+            .filter(|&j| self.boundary[j])
+            .collect();
+        starts.into_iter().map(|start| {
+            let end = (start + 1..self.chars.len())
+                .find(|&j| self.boundary[j] || !self.chars[j].is_alphanumeric())
+                .unwrap_or(self.chars.len());
+            start..end
+        })
+    }
+
+    /// Merge matched char positions into sorted byte ranges of the original text.
+    fn ranges(&self, mut positions: Vec<usize>) -> Vec<Range<usize>> {
+        positions.sort_unstable();
+        positions.dedup();
+
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for p in positions {
+            let start = self.offsets[p];
+            let end = self.offsets.get(p + 1).copied().unwrap_or(self.len);
+            match ranges.last_mut() {
+                Some(last) if last.end == start => last.end = end,
+                _ => ranges.push(start..end),
+            }
+        }
+        ranges
+    }
+}
+
+/// Optimal string alignment distance: edits plus adjacent transpositions.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let width = b.len() + 1;
+    let mut d: Vec<usize> = vec![0; (a.len() + 1) * width];
+    for i in 0..=a.len() {
+        d[i * width] = i;
+    }
+    for (j, cell) in d.iter_mut().enumerate().take(width) {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (d[(i - 1) * width + j] + 1)
+                .min(d[i * width + j - 1] + 1)
+                .min(d[(i - 1) * width + j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(d[(i - 2) * width + j - 2] + 1);
+            }
+            d[i * width + j] = best;
+        }
+    }
+    d[a.len() * width + b.len()]
+}
@@ -3,12 +3,16 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
+use crate::catalog::WingetOverrides;
+pub use crate::catalog_types::{Architecture, InstallScope};
+
 // ── Settings tab ─────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum SettingsTab {
     #[default]
     Winget,
+    Catalog,
     Changelog,
 }
 
@@ -36,22 +40,6 @@ impl fmt::Display for InstallMode {
 
 // ── Install scope ────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum InstallScope {
-    User,
-    Machine,
-}
-
-impl fmt::Display for InstallScope {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::User => write!(f, "User"),
-            Self::Machine => write!(f, "Machine"),
-        }
-    }
-}
-
 /// Newtype so `pick_list` can display "Default" for `None`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct OptionalScope(pub Option<InstallScope>);
@@ -75,26 +63,6 @@ impl fmt::Display for OptionalScope {
 
 // ── Architecture ─────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum Architecture {
-    #[serde(rename = "x86")]
-    X86,
-    #[serde(rename = "x64")]
-    X64,
-    #[serde(rename = "arm64")]
-    Arm64,
-}
-
-impl fmt::Display for Architecture {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::X86 => write!(f, "x86"),
-            Self::X64 => write!(f, "x64"),
-            Self::Arm64 => write!(f, "arm64"),
-        }
-    }
-}
-
 /// Newtype so `pick_list` can display "Default" for `None`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct OptionalArchitecture(pub Option<Architecture>);
@@ -117,9 +85,61 @@ impl fmt::Display for OptionalArchitecture {
     }
 }
 
+// ── Catalog sources ──────────────────────────────────────────────
+
+/// A place to load the package catalog from: an HTTPS URL, a `file://` URL,
+/// or a local/UNC path to `packages.toml` or the folder containing it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogSourceEntry {
+    pub location: String,
+    pub enabled: bool,
+    /// Minisign public key this source is trusted with instead of the
+    /// built-in one, e.g. for a mirror that signs its own catalog.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+}
+
+fn default_catalog_sources() -> Vec<CatalogSourceEntry> {
+    vec![CatalogSourceEntry {
+        location: crate::catalog::DEFAULT_CATALOG_URL.to_string(),
+        enabled: true,
+        public_key: None,
+    }]
+}
+
+/// The `[catalog]` section of the settings file.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CatalogSettings {
+    /// Catalog sources in priority order; the first one that answers wins.
+    #[serde(default = "default_catalog_sources")]
+    pub sources: Vec<CatalogSourceEntry>,
+}
+
+impl Default for CatalogSettings {
+    fn default() -> Self {
+        Self {
+            sources: default_catalog_sources(),
+        }
+    }
+}
+
+impl CatalogSettings {
+    /// The enabled catalog sources, highest priority first.
+    pub fn enabled_sources(&self) -> Vec<crate::catalog::Source> {
+        self.sources
+            .iter()
+            .filter(|s| s.enabled)
+            .map(|s| crate::catalog::Source {
+                location: s.location.clone(),
+                public_key: s.public_key.clone(),
+            })
+            .collect()
+    }
+}
+
 // ── Winget settings ──────────────────────────────────────────────
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct WingetSettings {
     pub install_mode: InstallMode,
     pub scope: Option<InstallScope>,
@@ -149,23 +169,55 @@ impl Default for WingetSettings {
 impl WingetSettings {
     /// Build extra CLI flags for install/upgrade commands.
     pub fn install_args(&self) -> Vec<String> {
+        self.install_args_for(&WingetOverrides::default())
+    }
+
+    /// Flags for `winget uninstall`: the install mode, `--force`, and
+    /// `--disable-interactivity`. The other settings only apply to installs.
+    pub fn uninstall_args(&self) -> Vec<String> {
+        let mut args = vec![match self.install_mode {
+            InstallMode::Silent => "--silent".to_string(),
+            InstallMode::Interactive => "--interactive".to_string(),
+        }];
+        if self.force {
+            args.push("--force".into());
+        }
+        if self.disable_interactivity {
+            args.push("--disable-interactivity".into());
+        }
+        args
+    }
+
+    /// Build install flags for one package, merging its catalog overrides
+    /// over these settings (see `WingetOverrides` for the precedence).
+    pub fn install_args_for(&self, overrides: &WingetOverrides) -> Vec<String> {
         let mut args = Vec::new();
 
-        match self.install_mode {
-            InstallMode::Silent => args.push("--silent".into()),
-            InstallMode::Interactive => args.push("--interactive".into()),
+        if let Some(ref switches) = overrides.override_args {
+            args.push("--override".into());
+            args.push(switches.clone());
+        } else {
+            match self.install_mode {
+                InstallMode::Silent => args.push("--silent".into()),
+                InstallMode::Interactive => args.push("--interactive".into()),
+            }
         }
 
-        if let Some(scope) = &self.scope {
+        if let Some(scope) = overrides.scope.or(self.scope) {
             args.push("--scope".into());
             args.push(scope.to_string().to_lowercase());
         }
 
-        if let Some(arch) = &self.architecture {
+        if let Some(arch) = overrides.architecture.or(self.architecture) {
             args.push("--architecture".into());
             args.push(arch.to_string());
         }
 
+        if let Some(ref locale) = overrides.locale {
+            args.push("--locale".into());
+            args.push(locale.clone());
+        }
+
         if self.force {
             args.push("--force".into());
         }
@@ -183,10 +235,28 @@ impl WingetSettings {
             args.push(self.install_location.clone());
         }
 
+        if let Some(ref switches) = overrides.custom {
+            args.push("--custom".into());
+            args.push(switches.clone());
+        }
+
+        args.extend(overrides.extra_args.iter().cloned());
         args
     }
 }
 
+// ── Settings file ────────────────────────────────────────────────
+
+/// Everything in settings.toml. The winget options stay at the top level,
+/// where files from before the `[catalog]` section have them.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(flatten)]
+    pub winget: WingetSettings,
+    #[serde(default)]
+    pub catalog: CatalogSettings,
+}
+
 // ── Persistence ─────────────────────────────────────────────────
 
 fn settings_path() -> Option<PathBuf> {
@@ -196,18 +266,18 @@ fn settings_path() -> Option<PathBuf> {
 }
 
 /// Load settings from disk. Returns `Default` on any failure.
-pub fn load_settings() -> WingetSettings {
+pub fn load_settings() -> Settings {
     let Some(path) = settings_path() else {
-        return WingetSettings::default();
+        return Settings::default();
     };
     let Ok(raw) = std::fs::read_to_string(&path) else {
-        return WingetSettings::default();
+        return Settings::default();
     };
     toml::from_str(&raw).unwrap_or_default()
 }
 
 /// Serialize settings to a TOML string for async persistence.
-pub fn serialize_settings(settings: &WingetSettings) -> Option<String> {
+pub fn serialize_settings(settings: &Settings) -> Option<String> {
     toml::to_string_pretty(settings).ok()
 }
 
@@ -221,3 +291,39 @@ pub async fn save_settings(content: String) {
     }
     let _ = tokio::fs::write(&path, content).await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_without_a_catalog_section_still_load() {
+        let raw = r#"install_mode = "interactive"
+force = true
+include_unknown = true
+ignore_security_hash = false
+disable_interactivity = false
+install_location = ""
+"#;
+        let settings: Settings = toml::from_str(raw).unwrap();
+        assert_eq!(settings.winget.install_mode, InstallMode::Interactive);
+        assert!(settings.winget.force);
+        assert_eq!(settings.catalog.sources, default_catalog_sources());
+    }
+
+    #[test]
+    fn catalog_sources_round_trip() {
+        let mut settings = Settings::default();
+        settings.catalog.sources.push(CatalogSourceEntry {
+            location: r"\\server\share\catalog".into(),
+            enabled: false,
+            public_key: Some("RWRXxZDBBuxod21eAPELnv6DZRs4A0QFchtln3ZZyxqV+0Xdqb3LL/fr".into()),
+        });
+
+        let raw = serialize_settings(&settings).unwrap();
+        assert!(raw.contains("[[catalog.sources]]"), "{raw}");
+        let loaded: Settings = toml::from_str(&raw).unwrap();
+        assert_eq!(loaded.catalog.sources, settings.catalog.sources);
+        assert_eq!(loaded.winget.install_mode, settings.winget.install_mode);
+    }
+}
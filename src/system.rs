@@ -0,0 +1,117 @@
+//! Detection of the machine Provision runs on, so packages that can't run
+//! here (wrong architecture, too old or too new a Windows build) are greyed
+//! out in the picker instead of failing during install.
+
+use tokio::process::Command;
+
+use crate::settings::Architecture;
+
+/// What we know about this machine. `None` means detection failed, in which
+/// case no constraint on that property is enforced.
+#[derive(Debug, Clone, Default)]
+pub struct SystemInfo {
+    pub arch: Option<Architecture>,
+    /// Windows build number, e.g. `22631` for Windows 11 23H2.
+    pub os_build: Option<u32>,
+}
+
+/// Detect the native architecture and Windows build.
+pub async fn detect() -> SystemInfo {
+    SystemInfo {
+        arch: native_arch(),
+        os_build: os_build().await,
+    }
+}
+
+/// The native processor architecture, even when this process runs emulated
+/// (an x64 build on an ARM64 machine reports AMD64 in its environment).
+#[cfg(windows)]
+fn native_arch() -> Option<Architecture> {
+    use windows_sys::Win32::System::SystemInformation::{
+        IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_ARM64, IMAGE_FILE_MACHINE_I386,
+    };
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, IsWow64Process2};
+
+    let mut process = 0;
+    let mut native = 0;
+    let ok = unsafe { IsWow64Process2(GetCurrentProcess(), &mut process, &mut native) };
+    if ok == 0 {
+        return env_arch();
+    }
+    match native {
+        IMAGE_FILE_MACHINE_AMD64 => Some(Architecture::X64),
+        IMAGE_FILE_MACHINE_ARM64 => Some(Architecture::Arm64),
+        IMAGE_FILE_MACHINE_I386 => Some(Architecture::X86),
+        _ => env_arch(),
+    }
+}
+
+#[cfg(not(windows))]
+fn native_arch() -> Option<Architecture> {
+    env_arch()
+}
+
+/// Architecture from the environment. Under WOW64, `PROCESSOR_ARCHITEW6432`
+/// holds the native value and `PROCESSOR_ARCHITECTURE` the emulated one.
+fn env_arch() -> Option<Architecture> {
+    let value = std::env::var("PROCESSOR_ARCHITEW6432")
+        .or_else(|_| std::env::var("PROCESSOR_ARCHITECTURE"))
+        .ok()?;
+    match value.to_ascii_uppercase().as_str() {
+        "AMD64" => Some(Architecture::X64),
+        "ARM64" => Some(Architecture::Arm64),
+        "X86" => Some(Architecture::X86),
+        _ => None,
+    }
+}
+
+/// Windows build number from `ver`, which prints e.g.
+/// `Microsoft Windows [Version 10.0.22631.4460]` (the word is localized).
+async fn os_build() -> Option<u32> {
+    let output = Command::new("cmd")
+        .args(["/C", "ver"])
+        .stderr(std::process::Stdio::null())
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .await
+        .ok()?;
+    parse_ver(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Third component of the first `major.minor.build[.revision]` token.
+fn parse_ver(output: &str) -> Option<u32> {
+    output
+        .split(|c: char| c.is_whitespace() || c == '[' || c == ']')
+        .map(|token| token.split('.').collect::<Vec<_>>())
+        .find(|parts| parts.len() >= 3 && parts.iter().all(|p| p.parse::<u32>().is_ok()))
+        .and_then(|parts| parts[2].parse().ok())
+}
+
+/// Expand `%VAR%` references the way cmd does; unknown variables are left
+/// as written.
+pub fn expand_env_vars(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('%') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('%') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let name = &after[..end];
+        match std::env::var(name) {
+            Ok(value) if !name.is_empty() => {
+                out.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            // Keep the first % and retry from the second, so `100% %TEMP%` works
+            _ => {
+                out.push('%');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,100 @@
+//! Removing software: catalog packages through their uninstall command, and
+//! anything else `winget list` found through `winget uninstall`.
+
+use iced::futures;
+use iced::futures::SinkExt as _;
+use iced::stream;
+
+use crate::catalog::Package;
+use crate::install::{self, InstallProgress, Sender};
+use crate::installer;
+use crate::upgrade::InstalledPackage;
+
+/// One package to remove.
+#[derive(Debug, Clone)]
+pub struct Removal {
+    pub name: String,
+    /// Key into the installed-package map, dropped once the removal succeeds.
+    pub key: String,
+    /// Program and arguments; `None` if the package has no way to uninstall.
+    pub command: Option<(String, Vec<String>)>,
+}
+
+impl Removal {
+    pub fn for_package(pkg: &Package, key: &str, winget_args: &[String]) -> Self {
+        Self {
+            name: pkg.name.clone(),
+            key: key.to_string(),
+            command: pkg.uninstaller(winget_args),
+        }
+    }
+
+    /// Software outside the catalog, removed by its winget ID.
+    pub fn for_installed(pkg: &InstalledPackage, winget_args: &[String]) -> Self {
+        let name = if pkg.name.is_empty() {
+            &pkg.id
+        } else {
+            &pkg.name
+        };
+        Self {
+            name: name.clone(),
+            key: pkg.key.clone(),
+            command: Some(installer::winget_uninstall(&pkg.id, winget_args)),
+        }
+    }
+
+    /// The command line, for the review screen and dry runs.
+    pub fn describe(&self) -> Option<String> {
+        let (program, args) = self.command.as_ref()?;
+        Some(installer::describe(program, args))
+    }
+}
+
+pub fn uninstall_all(
+    removals: Vec<Removal>,
+    dry_run: bool,
+) -> impl futures::Stream<Item = InstallProgress> + Send {
+    stream::channel(100, move |mut sender: Sender| async move {
+        for (i, removal) in removals.iter().enumerate() {
+            let _ = sender.send(InstallProgress::Started { index: i }).await;
+
+            let Some((ref program, ref args)) = removal.command else {
+                let _ = sender
+                    .send(InstallProgress::Failed {
+                        index: i,
+                        error: "No uninstall method defined".into(),
+                    })
+                    .await;
+                continue;
+            };
+
+            if dry_run {
+                let _ = sender
+                    .send(InstallProgress::Log {
+                        index: i,
+                        line: format!(
+                            "[DRY RUN] Would run: {}",
+                            installer::describe(program, args)
+                        ),
+                    })
+                    .await;
+
+                tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+                let _ = sender.send(InstallProgress::Succeeded { index: i }).await;
+                continue;
+            }
+
+            match install::run_command(program, args, i, &mut sender).await {
+                Ok(()) => {
+                    let _ = sender.send(InstallProgress::Succeeded { index: i }).await;
+                }
+                Err(e) => {
+                    let _ = sender
+                        .send(InstallProgress::Failed { index: i, error: e })
+                        .await;
+                }
+            }
+        }
+        let _ = sender.send(InstallProgress::Completed).await;
+    })
+}
@@ -3,14 +3,38 @@ use iced::futures::SinkExt as _;
 use iced::stream;
 use tokio::process::Command;
 
+use crate::catalog::VersionPin;
+use crate::detect::Detect;
 use crate::install::{self, InstallProgress, LineEvent, Sender};
+use crate::installer::{self, Backend};
+use crate::search::{Item, Match, Query};
 
 #[derive(Debug, Clone)]
 pub struct InstalledPackage {
-    pub winget_id: String,
+    /// Lowercase winget ID, `backend:name` for other installers, or
+    /// `detect:<package id>` for detect rules.
+    pub key: String,
+    /// The ID as listed, which `winget uninstall -e` matches case-sensitively.
+    pub id: String,
+    pub name: String,
     pub version: String,
 }
 
+impl InstalledPackage {
+    /// Fuzzy-match this package's name and ID.
+    pub fn search_match(&self, query: &Query) -> Option<Match> {
+        crate::search::match_item(
+            query,
+            &Item {
+                name: &self.name,
+                description: "",
+                id: Some(&self.id),
+                tags: &[],
+            },
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum InstalledScanProgress {
     Activity {
@@ -34,10 +58,33 @@ pub struct UpgradeablePackage {
     pub available_version: String,
     #[allow(dead_code)]
     pub source: String,
-    /// Precomputed `name.to_lowercase()` for search filtering.
-    pub name_lower: String,
-    /// Precomputed `winget_id.to_lowercase()` for search filtering.
-    pub winget_id_lower: String,
+    /// The catalog's version pin, set when the available version breaks it.
+    pub pin: Option<VersionPin>,
+}
+
+impl UpgradeablePackage {
+    /// Fuzzy-match this package's name and winget ID.
+    pub fn search_match(&self, query: &Query) -> Option<Match> {
+        crate::search::match_item(
+            query,
+            &Item {
+                name: &self.name,
+                description: "",
+                id: Some(&self.winget_id),
+                tags: &[],
+            },
+        )
+    }
+}
+
+/// A row of `winget search` output.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub name: String,
+    pub id: String,
+    pub version: String,
+    /// `winget` or `msstore`.
+    pub source: String,
 }
 
 #[derive(Debug, Clone)]
@@ -48,7 +95,13 @@ pub enum ScanProgress {
     Failed { error: String },
 }
 
-pub fn scan_installed(dry_run: bool) -> impl futures::Stream<Item = InstalledScanProgress> + Send {
+/// Scan `winget list`, then each of `backends` that can list its packages,
+/// then evaluate the catalog's detect rules (installed-map key and rule).
+pub fn scan_installed(
+    dry_run: bool,
+    backends: Vec<Backend>,
+    detect_rules: Vec<(String, Detect)>,
+) -> impl futures::Stream<Item = InstalledScanProgress> + Send {
     stream::channel(
         100,
         move |mut sender: futures::channel::mpsc::Sender<InstalledScanProgress>| async move {
@@ -63,25 +116,48 @@ pub fn scan_installed(dry_run: bool) -> impl futures::Stream<Item = InstalledSca
 
                 let fake = vec![
                     InstalledPackage {
-                        winget_id: "Git.Git".into(),
+                        key: "git.git".into(),
+                        id: "Git.Git".into(),
+                        name: "Git".into(),
                         version: "2.47.0".into(),
                     },
                     InstalledPackage {
-                        winget_id: "Mozilla.Firefox".into(),
+                        key: "mozilla.firefox".into(),
+                        id: "Mozilla.Firefox".into(),
+                        name: "Mozilla Firefox".into(),
                         version: "131.0".into(),
                     },
                     InstalledPackage {
-                        winget_id: "7zip.7zip".into(),
+                        key: "7zip.7zip".into(),
+                        id: "7zip.7zip".into(),
+                        name: "7-Zip".into(),
                         version: "24.08".into(),
                     },
                     InstalledPackage {
-                        winget_id: "Microsoft.WindowsTerminal".into(),
+                        key: "microsoft.windowsterminal".into(),
+                        id: "Microsoft.WindowsTerminal".into(),
+                        name: "Windows Terminal".into(),
                         version: "1.21.0".into(),
                     },
                     InstalledPackage {
-                        winget_id: "Microsoft.VisualStudioCode".into(),
+                        key: "microsoft.visualstudiocode".into(),
+                        id: "Microsoft.VisualStudioCode".into(),
+                        name: "Microsoft Visual Studio Code".into(),
                         version: "1.95.0".into(),
                     },
+                    // Preinstalled, not in the catalog
+                    InstalledPackage {
+                        key: "9wzdncrfj3q2".into(),
+                        id: "9WZDNCRFJ3Q2".into(),
+                        name: "MSN Weather".into(),
+                        version: "4.53.52331.0".into(),
+                    },
+                    InstalledPackage {
+                        key: "detect:uv".into(),
+                        id: "uv".into(),
+                        name: "uv".into(),
+                        version: "0.5.11".into(),
+                    },
                 ];
 
                 let _ = sender
@@ -132,7 +208,23 @@ pub fn scan_installed(dry_run: bool) -> impl futures::Stream<Item = InstalledSca
 
             let _ = child.wait().await;
 
-            let packages = parse_list_table(&all_lines);
+            let mut packages = parse_list_table(&all_lines);
+            for backend in backends {
+                let _ = sender
+                    .send(InstalledScanProgress::Activity {
+                        line: format!("Checking {backend} packages..."),
+                    })
+                    .await;
+                packages.extend(backend_packages(backend).await);
+            }
+            if !detect_rules.is_empty() {
+                let _ = sender
+                    .send(InstalledScanProgress::Activity {
+                        line: "Checking detect rules...".into(),
+                    })
+                    .await;
+            }
+            packages.extend(detected_packages(detect_rules).await);
             let _ = sender
                 .send(InstalledScanProgress::Completed { packages })
                 .await;
@@ -140,6 +232,57 @@ pub fn scan_installed(dry_run: bool) -> impl futures::Stream<Item = InstalledSca
     )
 }
 
+/// Rescan only `backends` and `detect_rules`, for a catalog that arrives
+/// after the startup scan and uses backends or rules it didn't cover.
+/// Finds nothing in a dry run.
+pub async fn scan_catalog_installed(
+    dry_run: bool,
+    backends: Vec<Backend>,
+    detect_rules: Vec<(String, Detect)>,
+) -> Vec<InstalledPackage> {
+    if dry_run {
+        return Vec::new();
+    }
+    let mut packages = Vec::new();
+    for backend in backends {
+        packages.extend(backend_packages(backend).await);
+    }
+    packages.extend(detected_packages(detect_rules).await);
+    packages
+}
+
+async fn backend_packages(backend: Backend) -> Vec<InstalledPackage> {
+    let found = backend.scan_installed().await;
+    found
+        .into_iter()
+        .map(|(key, version)| {
+            let name = key.split_once(':').map_or("", |(_, n)| n).to_string();
+            InstalledPackage {
+                id: name.clone(),
+                name,
+                key,
+                version,
+            }
+        })
+        .collect()
+}
+
+async fn detected_packages(detect_rules: Vec<(String, Detect)>) -> Vec<InstalledPackage> {
+    let mut packages = Vec::new();
+    for (key, rule) in detect_rules {
+        if let Some(version) = rule.check().await {
+            let id = key.trim_start_matches("detect:").to_string();
+            packages.push(InstalledPackage {
+                name: id.clone(),
+                id,
+                key,
+                version,
+            });
+        }
+    }
+    packages
+}
+
 pub fn parse_list_table(lines: &[String]) -> Vec<InstalledPackage> {
     let header_idx = lines
         .iter()
@@ -175,12 +318,15 @@ pub fn parse_list_table(lines: &[String]) -> Vec<InstalledPackage> {
             safe_slice_to_end(line, version_col)
         };
 
-        if id.is_empty() {
+        // An ID cut short to fit the table can't be passed to `--id -e`
+        if id.is_empty() || is_truncated(&id) {
             continue;
         }
 
         packages.push(InstalledPackage {
-            winget_id: id.to_lowercase(),
+            key: id.to_lowercase(),
+            name: safe_slice(line, 0, id_col),
+            id,
             version,
         });
     }
@@ -219,8 +365,7 @@ pub fn scan_upgrades(
                         current_version: "130.0".into(),
                         available_version: "131.0".into(),
                         source: "winget".into(),
-                        name_lower: "mozilla firefox".into(),
-                        winget_id_lower: "mozilla.firefox".into(),
+                        pin: None,
                     },
                     UpgradeablePackage {
                         name: "Visual Studio Code".into(),
@@ -228,8 +373,7 @@ pub fn scan_upgrades(
                         current_version: "1.94.0".into(),
                         available_version: "1.95.0".into(),
                         source: "winget".into(),
-                        name_lower: "visual studio code".into(),
-                        winget_id_lower: "microsoft.visualstudiocode".into(),
+                        pin: None,
                     },
                     UpgradeablePackage {
                         name: "Git".into(),
@@ -237,8 +381,7 @@ pub fn scan_upgrades(
                         current_version: "2.46.0".into(),
                         available_version: "2.47.0".into(),
                         source: "winget".into(),
-                        name_lower: "git".into(),
-                        winget_id_lower: "git.git".into(),
+                        pin: None,
                     },
                 ];
 
@@ -354,26 +497,118 @@ pub fn parse_upgrade_table(lines: &[String]) -> Vec<UpgradeablePackage> {
             (safe_slice_to_end(line, available_col), String::new())
         };
 
-        if id.is_empty() || available.is_empty() {
+        if id.is_empty() || is_truncated(&id) || available.is_empty() {
             continue;
         }
 
-        let name_lower = name.to_lowercase();
-        let winget_id_lower = id.to_lowercase();
         packages.push(UpgradeablePackage {
             name,
             winget_id: id,
             current_version: version,
             available_version: available,
             source,
-            name_lower,
-            winget_id_lower,
+            pin: None,
         });
     }
 
     packages
 }
 
+/// Run `winget search` for `query`, at most 50 results. Rows whose ID
+/// winget truncated are left out.
+pub async fn search_winget(query: String, dry_run: bool) -> Result<Vec<SearchResult>, String> {
+    if dry_run {
+        tokio::time::sleep(std::time::Duration::from_millis(600)).await;
+        let fake = [
+            ("Git", "Git.Git", "2.47.1", "winget"),
+            ("GitKraken", "Axosoft.GitKraken", "10.5.0", "winget"),
+            ("Fork", "Fork.Fork", "2.1.0", "winget"),
+            ("Git Credential Manager", "Git.GCM", "2.6.0", "winget"),
+            ("Microsoft To Do", "9NBLGGH5R558", "Unknown", "msstore"),
+        ];
+        let needle = query.to_lowercase();
+        return Ok(fake
+            .into_iter()
+            .filter(|(name, id, ..)| {
+                name.to_lowercase().contains(&needle) || id.to_lowercase().contains(&needle)
+            })
+            .map(|(name, id, version, source)| SearchResult {
+                name: name.into(),
+                id: id.into(),
+                version: version.into(),
+                source: source.into(),
+            })
+            .collect());
+    }
+
+    let output = Command::new("winget")
+        .args(["search", "--query", &query, "--count", "50"])
+        .arg("--accept-source-agreements")
+        .stderr(std::process::Stdio::null())
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .await
+        .map_err(|e| format!("Failed to spawn winget: {e}"))?;
+    Ok(parse_search_table(&output_lines(&output.stdout)))
+}
+
+pub fn parse_search_table(lines: &[String]) -> Vec<SearchResult> {
+    let header_idx = lines
+        .iter()
+        .position(|l| l.contains("Name") && l.contains("Id") && l.contains("Version"));
+
+    let Some(header_idx) = header_idx else {
+        return Vec::new();
+    };
+
+    let header = &lines[header_idx];
+
+    let Some(id_col) = header.find("Id") else {
+        return Vec::new();
+    };
+    let Some(version_col) = header.find("Version") else {
+        return Vec::new();
+    };
+    let source_col = header.find("Source");
+    // Match (e.g. "Tag: git") only appears when a result matched on it
+    let version_end = header.find("Match").or(source_col).unwrap_or(usize::MAX);
+    let data_start = find_data_start(lines, header_idx);
+
+    let mut results = Vec::new();
+
+    for line in &lines[data_start..] {
+        if line.len() < version_col + 1 {
+            continue;
+        }
+
+        // A truncated ID can't be installed with `--id -e`
+        let id = safe_slice(line, id_col, version_col);
+        if id.is_empty() || is_truncated(&id) {
+            continue;
+        }
+
+        results.push(SearchResult {
+            name: safe_slice(line, 0, id_col),
+            id,
+            version: safe_slice(line, version_col, version_end),
+            source: source_col
+                .map(|col| safe_slice_to_end(line, col))
+                .unwrap_or_default(),
+        });
+    }
+
+    results
+}
+
+/// Lines of captured winget output. Progress spinners are redrawn with
+/// `\r`, so only what's left on each line is kept.
+pub fn output_lines(stdout: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .map(|line| line.rsplit('\r').next().unwrap_or_default().to_string())
+        .collect()
+}
+
 /// Find the first data row after the header, skipping any separator line (dashes).
 fn find_data_start(lines: &[String], header_idx: usize) -> usize {
     let sep_offset = lines[header_idx + 1..].iter().position(|l| {
@@ -399,6 +634,12 @@ fn snap_back(s: &str, mut i: usize) -> usize {
     i
 }
 
+/// Whether winget shortened a column value with an ellipsis to fit the
+/// console width.
+fn is_truncated(value: &str) -> bool {
+    value.ends_with('\u{2026}')
+}
+
 fn safe_slice(line: &str, start: usize, end: usize) -> String {
     let start = snap_forward(line, start.min(line.len()));
     let end = snap_back(line, end.min(line.len()));
@@ -416,22 +657,34 @@ fn safe_slice_to_end(line: &str, start: usize) -> String {
     line[start..].trim().to_string()
 }
 
+/// Upgrade `packages` in order. `extra_args` holds the winget flags for
+/// each package (see `WingetSettings::install_args_for`).
 pub fn upgrade_all(
     packages: Vec<UpgradeablePackage>,
     dry_run: bool,
-    extra_args: Vec<String>,
+    extra_args: Vec<Vec<String>>,
 ) -> impl futures::Stream<Item = InstallProgress> + Send {
     stream::channel(100, move |mut sender: Sender| async move {
-        for (i, pkg) in packages.iter().enumerate() {
+        for (i, (pkg, extra_args)) in packages.iter().zip(&extra_args).enumerate() {
             let _ = sender.send(InstallProgress::Started { index: i }).await;
 
+            let mut args: Vec<String> = vec![
+                "upgrade".into(),
+                "--id".into(),
+                pkg.winget_id.clone(),
+                "-e".into(),
+                "--accept-package-agreements".into(),
+                "--accept-source-agreements".into(),
+            ];
+            args.extend(extra_args.iter().cloned());
+
             if dry_run {
                 let _ = sender
                     .send(InstallProgress::Log {
                         index: i,
                         line: format!(
-                            "[DRY RUN] Would run: winget upgrade --id {} -e",
-                            pkg.winget_id
+                            "[DRY RUN] Would run: {}",
+                            installer::describe("winget", &args)
                         ),
                     })
                     .await;
@@ -441,16 +694,6 @@ pub fn upgrade_all(
                 continue;
             }
 
-            let mut args: Vec<String> = vec![
-                "upgrade".into(),
-                "--id".into(),
-                pkg.winget_id.clone(),
-                "-e".into(),
-                "--accept-package-agreements".into(),
-                "--accept-source-agreements".into(),
-            ];
-            args.extend(extra_args.iter().cloned());
-
             match install::run_command("winget", &args, i, &mut sender).await {
                 Ok(()) => {
                     let _ = sender.send(InstallProgress::Succeeded { index: i }).await;
@@ -465,3 +708,41 @@ pub fn upgrade_all(
         let _ = sender.send(InstallProgress::Completed).await;
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn list_skips_truncated_ids() {
+        let table = lines(
+            "Name              Id                    Version  Source
+-------------------------------------------------------
+Git               Git.Git               2.47.0   winget
+Some Long App     Publisher.SomeLong\u{2026}  1.0      winget
+",
+        );
+        let ids: Vec<String> = parse_list_table(&table).into_iter().map(|p| p.id).collect();
+        assert_eq!(ids, ["Git.Git"]);
+    }
+
+    #[test]
+    fn search_skips_truncated_ids() {
+        let table = lines(
+            "Name              Id                    Version  Match         Source
+---------------------------------------------------------------------
+Git               Git.Git               2.47.1   Tag: git      winget
+Git Extensions    GitExtensionsTeam.G\u{2026}  5.1      Tag: git      winget
+",
+        );
+        let ids: Vec<String> = parse_search_table(&table)
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(ids, ["Git.Git"]);
+    }
+}
@@ -1,14 +1,19 @@
+use std::ops::Range;
+
+use iced::widget::text::Span;
 use iced::widget::{
-    button, checkbox, column, container, pick_list, progress_bar, row, scrollable, text,
-    text_input, toggler,
+    button, checkbox, column, container, mouse_area, pick_list, progress_bar, rich_text, row,
+    scrollable, span, text, text_input, toggler,
 };
 use iced::{Element, Length, Theme, padding};
 
 use crate::catalog::{self, CatalogSource, Package};
-use crate::install::PackageStatus;
+use crate::install::{PackageStatus, StepStatus};
+use crate::installer::Installer;
 use crate::profile::Profile;
+use crate::search::{Match, Query};
 use crate::settings::{InstallMode, OptionalArchitecture, OptionalScope, SettingsTab};
-use crate::upgrade::UpgradeablePackage;
+use crate::upgrade::{InstalledPackage, SearchResult, UpgradeablePackage};
 use lucide_icons::Icon;
 
 use crate::styles::{
@@ -18,7 +23,7 @@ use crate::styles::{
     installed_badge_style, package_checkbox_style, tab_style, terminal_box_style,
     update_card_style, warning_badge_style,
 };
-use crate::{App, Message, ProgressState};
+use crate::{App, CatalogStatus, Message, ProgressState};
 
 impl App {
     pub(crate) fn view_profile_select(&self) -> Element<'_, Message> {
@@ -42,13 +47,16 @@ impl App {
             .spacing(8)
             .align_x(iced::Alignment::Center);
 
-        // Profile cards — top row: Personal + Work; bottom row: Manual (full width)
-        let [a, b, c] = Profile::ALL.map(|p| profile_card(p, self.selected_profile));
-
-        let top_row = row![a, b].spacing(10).width(Length::Fill);
-        let bottom_row = row![c].spacing(10).width(Length::Fill);
-
-        let grid = column![top_row, bottom_row].spacing(10).width(Length::Fill);
+        // Profile cards in rows of two; an odd last card spans the full width
+        let selected = self.selected_profile.as_deref();
+        let mut grid = column![].spacing(10).width(Length::Fill);
+        for pair in self.catalog.profiles.chunks(2) {
+            let mut cards = row![].spacing(10).width(Length::Fill);
+            for profile in pair {
+                cards = cards.push(profile_card(profile, selected));
+            }
+            grid = grid.push(cards);
+        }
 
         // Divider
         let divider = container(iced::widget::Space::new().height(1))
@@ -111,15 +119,20 @@ impl App {
             .style(update_card_style);
 
         // Catalog source indicator
-        let pkg_count = self.catalog.len();
+        let pkg_count = self.catalog.packages.len();
         let catalog_color = if self.catalog_source == CatalogSource::Remote {
             STATUS_GREEN
         } else {
             MUTED
         };
-        let catalog_label = match self.catalog_source.label_suffix() {
-            Some(suffix) => format!("{pkg_count} packages ({suffix})"),
-            None => format!("{pkg_count} packages"),
+        let mut suffixes: Vec<&str> = self.catalog_source.label_suffix().into_iter().collect();
+        if self.catalog.has_overlay {
+            suffixes.push("custom");
+        }
+        let catalog_label = if suffixes.is_empty() {
+            format!("{pkg_count} packages")
+        } else {
+            format!("{pkg_count} packages ({})", suffixes.join(", "))
         };
         let catalog_status = status_indicator(Icon::Package, catalog_label, catalog_color);
 
@@ -144,7 +157,7 @@ impl App {
         .spacing(12)
         .align_y(iced::Alignment::Center);
 
-        let content = column![
+        let mut content = column![
             heading_cluster,
             grid,
             divider,
@@ -156,6 +169,22 @@ impl App {
         .align_x(iced::Alignment::Center)
         .max_width(500);
 
+        if let Some(ref reason) = self.catalog_unreachable {
+            content = content.push(status_indicator(
+                Icon::CloudOff,
+                format!("Catalog not refreshed: {reason}"),
+                MUTED,
+            ));
+        }
+
+        for warning in &self.catalog.warnings {
+            content = content.push(status_indicator(
+                Icon::TriangleAlert,
+                warning.clone(),
+                STATUS_AMBER,
+            ));
+        }
+
         container(content)
             .center_x(Length::Fill)
             .center_y(Length::Fill)
@@ -164,25 +193,64 @@ impl App {
     }
 
     pub(crate) fn view_package_select(&self) -> Element<'_, Message> {
-        let profile = self.selected_profile.unwrap_or(Profile::Manual);
+        let title = if self.uninstall_mode {
+            "Uninstall"
+        } else {
+            self.selected_profile
+                .as_deref()
+                .and_then(|slug| self.catalog.profile(slug))
+                .map_or("Packages", |p| p.title.as_str())
+        };
 
-        let header = search_header(profile.title(), &self.search);
+        // Needs the installed-package scan to know what can be removed
+        let uninstall_toggle = toggler(self.uninstall_mode)
+            .label("Uninstall mode")
+            .text_size(13)
+            .size(18)
+            .on_toggle_maybe(
+                self.installed_scan_done
+                    .then_some(|_| Message::ToggleUninstallMode),
+            );
+        let header = search_header(title, &self.search).push(uninstall_toggle);
+        let listed = |p: &Package| !self.uninstall_mode || self.is_installed(p);
 
-        let search_lower = self.search.to_lowercase();
+        let query = Query::new(&self.search);
 
-        let categories = catalog::categories(&self.catalog);
+        let categories = self.catalog.categories();
         let mut pkg_list = column![].spacing(18).width(Length::Fill);
 
-        for cat in &categories {
+        // While searching, show one list ranked by relevance instead of categories
+        if !query.is_empty() {
+            let mut results: Vec<(&Package, Match)> = self
+                .catalog
+                .packages
+                .iter()
+                .filter(|p| listed(p))
+                .filter_map(|p| Some((p, self.package_match(p, &query)?)))
+                .collect();
+            results.sort_by_key(|(_, m)| std::cmp::Reverse(m.score));
+
+            let label = text(format!("{} RESULTS", results.len()))
+                .size(11)
+                .color(MUTED_FG);
+            let rows = results
+                .into_iter()
+                .map(|(pkg, m)| package_row(pkg, self, &m))
+                .collect();
+            pkg_list = pkg_list
+                .push(column![container(label).padding([2, 6]), two_columns(rows)].spacing(6));
+            if !self.uninstall_mode {
+                pkg_list = pkg_list.push(winget_search_section(self));
+            }
+        }
+
+        for cat in categories.iter().filter(|_| query.is_empty()) {
             let cat_packages: Vec<&Package> = self
                 .catalog
+                .packages
                 .iter()
-                .filter(|p| {
-                    p.category == *cat
-                        && (search_lower.is_empty()
-                            || p.name_lower.contains(&search_lower)
-                            || p.desc_lower.contains(&search_lower))
-                })
+                .filter(|p| p.category == *cat && listed(p))
+                .filter(|p| self.package_match(p, &query).is_some())
                 .collect();
 
             if cat_packages.is_empty() {
@@ -190,15 +258,12 @@ impl App {
             }
 
             // Count selected in this category
-            let selected_count = cat_packages
-                .iter()
-                .filter(|p| self.selected.contains(&p.id))
-                .count();
+            let selected_count = cat_packages.iter().filter(|p| self.is_checked(p)).count();
             let total_count = cat_packages.len();
 
             let cat_label_text = format!(
                 "{} \u{2014} {}/{}",
-                catalog::category_display_name(cat).to_uppercase(),
+                self.catalog.category_name(cat).to_uppercase(),
                 selected_count,
                 total_count,
             );
@@ -210,55 +275,127 @@ impl App {
                 Icon::Square
             };
 
-            let cat_label = button(
-                row![
-                    text(char::from(toggle_icon))
-                        .size(12)
-                        .font(LUCIDE_FONT)
-                        .color(MUTED_FG),
-                    text(cat_label_text).size(11).color(MUTED_FG),
-                ]
-                .spacing(6)
-                .align_y(iced::Alignment::Center),
-            )
-            .on_press(Message::ToggleCategory(cat.clone()))
-            .style(ghost_button_style)
-            .padding([2, 6]);
-
-            // Split packages into left/right columns
-            let half = cat_packages.len().div_ceil(2);
-            let left_pkgs = &cat_packages[..half];
-            let right_pkgs = &cat_packages[half..];
-
-            let mut left_col = column![].spacing(2);
-            let mut right_col = column![].spacing(2);
-
-            for pkg in left_pkgs {
-                left_col = left_col.push(package_row(pkg, self));
-            }
-            for pkg in right_pkgs {
-                right_col = right_col.push(package_row(pkg, self));
-            }
-
-            let two_col = row![
-                left_col.width(Length::FillPortion(1)),
-                right_col.width(Length::FillPortion(1)),
+            let mut cat_label_row = row![
+                text(char::from(toggle_icon))
+                    .size(12)
+                    .font(LUCIDE_FONT)
+                    .color(MUTED_FG),
             ]
-            .spacing(32);
+            .spacing(6)
+            .align_y(iced::Alignment::Center);
+            if let Some(icon) = self.catalog.category_icon(cat) {
+                cat_label_row =
+                    cat_label_row.push(text(icon).size(12).font(LUCIDE_FONT).color(MUTED));
+            }
+            let cat_label =
+                button(cat_label_row.push(text(cat_label_text).size(11).color(MUTED_FG)))
+                    .on_press(Message::ToggleCategory(cat.clone()))
+                    .style(ghost_button_style)
+                    .padding([2, 6]);
 
-            let cat_col = column![cat_label, two_col].spacing(6);
+            let rows = cat_packages
+                .iter()
+                .map(|pkg| package_row(pkg, self, &Match::default()))
+                .collect();
+            let cat_col = column![cat_label, two_columns(rows)].spacing(6);
 
             pkg_list = pkg_list.push(cat_col);
         }
 
+        if self.uninstall_mode {
+            let others: Vec<_> = self
+                .other_installed()
+                .into_iter()
+                .filter_map(|p| {
+                    if query.is_empty() {
+                        Some((p, Match::default()))
+                    } else {
+                        Some((p, p.search_match(&query)?))
+                    }
+                })
+                .collect();
+            if !others.is_empty() {
+                let selected_count = others
+                    .iter()
+                    .filter(|(p, _)| self.uninstall_selected.contains(&p.key))
+                    .count();
+                let label = text(format!(
+                    "OTHER INSTALLED SOFTWARE \u{2014} {selected_count}/{}",
+                    others.len()
+                ))
+                .size(11)
+                .color(MUTED_FG);
+                let rows = others
+                    .into_iter()
+                    .map(|(p, m)| installed_row(p, self, &m))
+                    .collect();
+                pkg_list = pkg_list
+                    .push(column![container(label).padding([2, 6]), two_columns(rows)].spacing(6));
+            }
+        }
+
         let scrollable_list = scrollable(pkg_list)
             .height(Length::Fill)
             .width(Length::Fill);
+        let details = self
+            .details_open
+            .as_ref()
+            .and_then(|id| self.catalog.packages.iter().find(|p| p.id == *id));
+        let list_area: Element<'_, Message> = match details {
+            Some(pkg) => row![scrollable_list, details_panel(pkg, self)]
+                .spacing(16)
+                .height(Length::Fill)
+                .into(),
+            None => scrollable_list.into(),
+        };
+
+        // Tag filter chips
+        let tags = self.catalog.tags();
+        let tag_chips: Element<'_, Message> = if tags.is_empty() {
+            iced::widget::Space::new().into()
+        } else {
+            iced::widget::Row::with_children(
+                tags.into_iter()
+                    .map(|tag| tag_chip(tag, self.active_tags.contains(tag))),
+            )
+            .spacing(6)
+            .wrap()
+            .into()
+        };
+
+        if self.uninstall_mode {
+            let count = self.uninstall_selected.len();
+            let footer_text = text(format!("{count} to remove")).size(13).color(MUTED);
+            let mut continue_btn = button(text("Continue").size(14))
+                .style(continue_button_style)
+                .padding([8, 20]);
+            if count > 0 {
+                continue_btn = continue_btn.on_press(Message::GoToReview);
+            }
+            let footer = row![
+                footer_text,
+                iced::widget::Space::new().width(Length::Fill),
+                continue_btn,
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center);
+
+            let content = column![header, tag_chips, list_area, footer]
+                .spacing(14)
+                .width(Length::Fill)
+                .height(Length::Fill);
+            return container(content)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding(28)
+                .into();
+        }
 
         // Footer
         let count = self.selected.len();
         let installed_selected = self
             .catalog
+            .packages
             .iter()
             .filter(|p| self.selected.contains(&p.id) && self.is_installed(p))
             .count();
@@ -324,7 +461,7 @@ impl App {
         .spacing(8)
         .align_y(iced::Alignment::Center);
 
-        let content = column![header, scrollable_list, footer]
+        let content = column![header, tag_chips, list_area, footer]
             .spacing(14)
             .width(Length::Fill)
             .height(Length::Fill);
@@ -337,10 +474,14 @@ impl App {
     }
 
     pub(crate) fn view_review(&self) -> Element<'_, Message> {
+        if self.uninstall_mode {
+            return self.view_uninstall_review();
+        }
         let header = back_header("Review");
 
         let queue: Vec<&Package> = self
             .catalog
+            .packages
             .iter()
             .filter(|p| self.selected.contains(&p.id))
             .collect();
@@ -357,7 +498,7 @@ impl App {
         };
         let subtitle = text(subtitle_text).size(13).color(MUTED);
 
-        let categories = catalog::categories(&self.catalog);
+        let categories = self.catalog.categories();
         let mut pkg_list = column![].spacing(14).width(Length::Fill);
 
         for cat in &categories {
@@ -366,9 +507,15 @@ impl App {
                 continue;
             }
 
-            let cat_label = text(catalog::category_display_name(cat).to_uppercase())
-                .size(11)
-                .color(MUTED_FG);
+            let mut cat_label = row![].spacing(6).align_y(iced::Alignment::Center);
+            if let Some(icon) = self.catalog.category_icon(cat) {
+                cat_label = cat_label.push(text(icon).size(12).font(LUCIDE_FONT).color(MUTED));
+            }
+            let cat_label = cat_label.push(
+                text(self.catalog.category_name(cat).to_uppercase())
+                    .size(11)
+                    .color(MUTED_FG),
+            );
 
             let mut cat_col = column![cat_label].spacing(4);
 
@@ -387,11 +534,10 @@ impl App {
                     .align_y(iced::Alignment::Center)
                     .into()
                 } else {
-                    let method = match (&pkg.install_command, &pkg.winget_id) {
-                        (Some(cmd), _) => cmd.clone(),
-                        (_, Some(wid)) => wid.clone(),
-                        _ => "unknown".into(),
-                    };
+                    let method = pkg
+                        .installer
+                        .as_ref()
+                        .map_or_else(|| "unknown".into(), Installer::summary);
                     text(method)
                         .size(11)
                         .font(iced::Font::MONOSPACE)
@@ -421,11 +567,52 @@ impl App {
 
                 cat_col = cat_col.push(pkg_row);
 
-                if let Some(ref post) = pkg.post_install {
-                    let post_text = text(format!("\u{21b3} post-install: {post}"))
+                if !pkg.depends_on.is_empty() {
+                    let names: Vec<&str> = pkg
+                        .depends_on
+                        .iter()
+                        .map(|dep| {
+                            self.catalog
+                                .packages
+                                .iter()
+                                .find(|p| p.id == *dep)
+                                .map_or(dep.as_str(), |p| p.name.as_str())
+                        })
+                        .collect();
+                    let deps_text = text(format!("\u{21b3} requires: {}", names.join(", ")))
+                        .size(11)
+                        .color(MUTED);
+                    cat_col = cat_col.push(container(deps_text).padding([2, 0]));
+                }
+
+                if let Some(ref pin) = pkg.version {
+                    let pin_text = text(format!("\u{21b3} version: {pin}"))
                         .size(11)
                         .color(MUTED);
-                    cat_col = cat_col.push(container(post_text).padding([2, 0]));
+                    cat_col = cat_col.push(container(pin_text).padding([2, 0]));
+                }
+
+                if pkg.winget_id.is_some()
+                    && let Some(summary) = pkg.winget.summary()
+                {
+                    let winget_text = text(format!("\u{21b3} winget: {summary}"))
+                        .size(11)
+                        .color(MUTED);
+                    cat_col = cat_col.push(container(winget_text).padding([2, 0]));
+                }
+
+                if let Some(ref post) = pkg.post_install {
+                    let steps = post.steps();
+                    for (n, step) in steps.iter().enumerate() {
+                        let prefix = match steps.len() {
+                            1 => "post-install".to_string(),
+                            total => format!("post-install {}/{total}", n + 1),
+                        };
+                        let post_text = text(format!("\u{21b3} {prefix}: {}", step.label()))
+                            .size(11)
+                            .color(MUTED);
+                        cat_col = cat_col.push(container(post_text).padding([2, 0]));
+                    }
                 }
             }
 
@@ -484,6 +671,107 @@ impl App {
             .into()
     }
 
+    fn view_uninstall_review(&self) -> Element<'_, Message> {
+        let header = back_header("Review");
+        let queue = self.removals();
+        let subtitle = text(format!("{} packages will be removed", queue.len()))
+            .size(13)
+            .color(MUTED);
+
+        let mut pkg_list = column![].spacing(4).width(Length::Fill);
+        for removal in &queue {
+            let method_widget: Element<'_, Message> = match removal.describe() {
+                Some(command) => text(command)
+                    .size(11)
+                    .font(iced::Font::MONOSPACE)
+                    .color(MUTED)
+                    .into(),
+                None => text("no uninstall method")
+                    .size(11)
+                    .color(STATUS_RED)
+                    .into(),
+            };
+            let pkg_row = row![
+                text(removal.name.clone()).size(14),
+                iced::widget::Space::new().width(Length::Fill),
+                method_widget,
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center)
+            .padding([4, 0]);
+            pkg_list = pkg_list.push(pkg_row);
+
+            // Installed packages that depend on this one and are staying
+            let needed_by: Vec<&str> = self
+                .catalog
+                .packages
+                .iter()
+                .find(|p| p.installed_key.as_deref() == Some(removal.key.as_str()))
+                .map(|removed| {
+                    self.catalog
+                        .packages
+                        .iter()
+                        .filter(|p| p.depends_on.contains(&removed.id))
+                        .filter(|p| self.is_installed(p) && !self.is_checked(p))
+                        .map(|p| p.name.as_str())
+                        .collect()
+                })
+                .unwrap_or_default();
+            if !needed_by.is_empty() {
+                let note = text(format!("\u{21b3} needed by: {}", needed_by.join(", ")))
+                    .size(11)
+                    .color(STATUS_AMBER);
+                pkg_list = pkg_list.push(container(note).padding([2, 0]));
+            }
+        }
+
+        let scrollable_list = scrollable(pkg_list)
+            .height(Length::Fill)
+            .width(Length::Fill);
+
+        let edit_btn = button(text("Edit").size(14))
+            .on_press(Message::GoBack)
+            .style(ghost_button_style)
+            .padding([8, 20]);
+        let uninstall_btn = button(text(format!("Uninstall {} packages", queue.len())).size(14))
+            .on_press(Message::StartUninstall)
+            .style(cancel_button_style)
+            .padding([8, 20]);
+        let footer = row![
+            iced::widget::Space::new().width(Length::Fill),
+            edit_btn,
+            uninstall_btn,
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let content = column![header, subtitle, scrollable_list, footer]
+            .spacing(14)
+            .width(Length::Fill)
+            .height(Length::Fill);
+
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(28)
+            .into()
+    }
+
+    pub(crate) fn view_uninstalling(&self) -> Element<'_, Message> {
+        view_progress_screen(
+            &self.uninstall,
+            &ProgressLabels {
+                verb: "Uninstalling",
+                done_label: "Uninstall",
+                dry_run_warning: "No packages will actually be removed",
+            },
+            self.uninstall_queue.iter().map(|r| r.name.as_str()),
+            self.dry_run,
+            Message::CancelUninstall,
+            Message::FinishUninstallAndReset,
+        )
+    }
+
     pub(crate) fn view_installing(&self) -> Element<'_, Message> {
         view_progress_screen(
             &self.install,
@@ -567,17 +855,20 @@ impl App {
 
         let header = search_header("Updates", &self.search);
 
-        let search_lower = self.search.to_lowercase();
+        let query = Query::new(&self.search);
 
-        let filtered_packages: Vec<&UpgradeablePackage> = scan
+        let mut filtered_packages: Vec<(&UpgradeablePackage, Match)> = scan
             .packages
             .iter()
-            .filter(|p| {
-                search_lower.is_empty()
-                    || p.name_lower.contains(&search_lower)
-                    || p.winget_id_lower.contains(&search_lower)
+            .filter_map(|p| {
+                if query.is_empty() {
+                    Some((p, Match::default()))
+                } else {
+                    Some((p, p.search_match(&query)?))
+                }
             })
             .collect();
+        filtered_packages.sort_by_key(|(_, m)| std::cmp::Reverse(m.score));
 
         let count = scan.selected.len();
         let total = scan.packages.len();
@@ -594,16 +885,15 @@ impl App {
 
         let mut pkg_list = column![].spacing(6).width(Length::Fill);
 
-        for pkg in &filtered_packages {
+        for (pkg, found) in &filtered_packages {
             let is_checked = scan.selected.contains(&pkg.winget_id);
-            let id = pkg.winget_id.clone();
 
-            let cb = checkbox(is_checked)
-                .label(&pkg.name)
-                .on_toggle(move |_| Message::ToggleUpgradePackage(id.clone()))
-                .size(16)
-                .text_size(14)
-                .style(package_checkbox_style);
+            let cb = highlighted_checkbox(
+                is_checked,
+                &pkg.name,
+                &found.name,
+                Some(Message::ToggleUpgradePackage(pkg.winget_id.clone())),
+            );
 
             let version_info = text(format!(
                 "{} \u{2192} {}  ({})",
@@ -615,6 +905,15 @@ impl App {
             let desc = container(version_info).padding(padding::left(26));
 
             pkg_list = pkg_list.push(cb).push(desc);
+
+            if let Some(ref pin) = pkg.pin {
+                let warning = text(format!(
+                    "Pinned to {pin} in the catalog \u{2014} upgrading breaks the pin"
+                ))
+                .size(12)
+                .color(STATUS_AMBER);
+                pkg_list = pkg_list.push(container(warning).padding(padding::left(26)));
+            }
         }
 
         let scrollable_list = scrollable(pkg_list)
@@ -671,12 +970,14 @@ impl App {
 
         let tab_bar = row![
             tab_button("Winget", SettingsTab::Winget, self.settings_tab),
+            tab_button("Catalog", SettingsTab::Catalog, self.settings_tab),
             tab_button("Changelog", SettingsTab::Changelog, self.settings_tab),
         ]
         .spacing(4);
 
         let tab_content: Element<'_, Message> = match self.settings_tab {
             SettingsTab::Winget => self.view_settings_winget(),
+            SettingsTab::Catalog => self.view_settings_catalog(),
             SettingsTab::Changelog => view_settings_changelog(),
         };
 
@@ -693,7 +994,7 @@ impl App {
     }
 
     fn view_settings_winget(&self) -> Element<'_, Message> {
-        let s = &self.settings;
+        let s = &self.settings.winget;
 
         let subtitle = text("Settings are saved automatically")
             .size(13)
@@ -805,6 +1106,146 @@ impl App {
             .width(Length::Fill)
             .into()
     }
+
+    fn view_settings_catalog(&self) -> Element<'_, Message> {
+        let subtitle = text("The package list is checked for updates once a day")
+            .size(13)
+            .color(MUTED);
+
+        let section_status = text("STATUS").size(11).color(MUTED_FG);
+
+        let source = match self.catalog_source {
+            CatalogSource::Embedded => "Built-in",
+            CatalogSource::Cached => "Cached download",
+            CatalogSource::Remote => "Downloaded",
+        };
+        let source_row = setting_row(
+            "Current catalog",
+            "Where the package list shown was loaded from",
+            text(format!(
+                "{source}, {} packages",
+                self.catalog.packages.len()
+            ))
+            .size(13)
+            .color(MUTED_FG)
+            .into(),
+        );
+
+        let label = if self.catalog_refreshing {
+            "Checking..."
+        } else {
+            "Refresh now"
+        };
+        let mut refresh_btn = button(
+            row![
+                text(char::from(Icon::RefreshCw)).size(14).font(LUCIDE_FONT),
+                text(label).size(13),
+            ]
+            .spacing(4)
+            .align_y(iced::Alignment::Center),
+        )
+        .style(ghost_button_style)
+        .padding([6, 12]);
+        if !self.catalog_refreshing {
+            refresh_btn = refresh_btn.on_press(Message::RefreshCatalog);
+        }
+        let refresh_row = setting_row(
+            "Refresh catalog",
+            "Check for a newer signed catalog right away",
+            refresh_btn.into(),
+        );
+
+        let mut settings_list = column![subtitle, section_status, source_row, refresh_row]
+            .spacing(12)
+            .padding(padding::right(20))
+            .width(Length::Fill);
+
+        if let Some(ref status) = self.catalog_status {
+            let (msg, color) = match status {
+                CatalogStatus::Success(msg) => (msg, STATUS_GREEN),
+                CatalogStatus::Error(msg) => (msg, STATUS_RED),
+            };
+            settings_list = settings_list.push(text(msg).size(12).color(color));
+        }
+
+        // ── Sources section ──────────────────────────────────────
+        let section_sources = text("SOURCES").size(11).color(MUTED_FG);
+        let signing = if catalog::requires_signatures() {
+            "Every catalog must be signed, with the built-in key or the source's own public key."
+        } else {
+            "This build has no signing key, so only sources with their own public key are \
+             signature-checked."
+        };
+        let sources_hint = text(format!(
+            "Tried from top to bottom: HTTPS URLs, file:// URLs, or local and network folders. \
+             {signing}"
+        ))
+        .size(12)
+        .color(MUTED);
+        settings_list = settings_list.push(section_sources).push(sources_hint);
+
+        let sources = &self.settings.catalog.sources;
+        for (i, source) in sources.iter().enumerate() {
+            let up = (i > 0).then(|| Message::MoveCatalogSource(i, i - 1));
+            let down = (i + 1 < sources.len()).then(|| Message::MoveCatalogSource(i, i + 1));
+            let location_color = if source.enabled { TEXT } else { MUTED };
+            let mut location = column![text(&source.location).size(13).color(location_color)]
+                .spacing(2)
+                .width(Length::Fill);
+            if source.public_key.is_some() {
+                location = location.push(text("Signed with its own key").size(11).color(MUTED));
+            }
+
+            settings_list = settings_list.push(
+                row![
+                    toggler(source.enabled)
+                        .on_toggle(move |v| Message::ToggleCatalogSource(i, v))
+                        .size(20),
+                    location,
+                    icon_button(Icon::ChevronUp, up),
+                    icon_button(Icon::ChevronDown, down),
+                    icon_button(Icon::X, Some(Message::RemoveCatalogSource(i))),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+            );
+        }
+
+        let new_source = text_input(
+            "https://..., file:///..., or \\\\server\\share\\folder",
+            &self.new_catalog_source,
+        )
+        .on_input(Message::NewCatalogSourceChanged)
+        .on_submit(Message::AddCatalogSource)
+        .padding(6)
+        .size(13)
+        .width(Length::Fill);
+        let new_source_key = text_input(
+            "Public key, if the source signs its own catalog (RW...)",
+            &self.new_catalog_source_key,
+        )
+        .on_input(Message::NewCatalogSourceKeyChanged)
+        .on_submit(Message::AddCatalogSource)
+        .padding(6)
+        .size(13)
+        .width(Length::Fill);
+        let mut add_btn = button(text("Add").size(13))
+            .style(ghost_button_style)
+            .padding([6, 12]);
+        if !self.new_catalog_source.trim().is_empty() {
+            add_btn = add_btn.on_press(Message::AddCatalogSource);
+        }
+        settings_list = settings_list.push(
+            row![column![new_source, new_source_key].spacing(6), add_btn]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+        );
+
+        scrollable(settings_list)
+            .height(Length::Fill)
+            .width(Length::Fill)
+            .into()
+    }
 }
 
 /// Back-button header: chevron-left icon button + heading text.
@@ -822,6 +1263,15 @@ fn back_header(title: &str) -> iced::widget::Row<'_, Message> {
         .align_y(iced::Alignment::Center)
 }
 
+/// Small icon-only ghost button; disabled when `on_press` is `None`.
+fn icon_button<'a>(icon: Icon, on_press: Option<Message>) -> Element<'a, Message> {
+    button(text(char::from(icon)).size(14).font(LUCIDE_FONT))
+        .on_press_maybe(on_press)
+        .style(ghost_button_style)
+        .padding([4, 6])
+        .into()
+}
+
 /// Back-button header with a search field on the right.
 fn search_header<'a>(title: &'a str, search: &'a str) -> iced::widget::Row<'a, Message> {
     let search_field = text_input("Search...", search)
@@ -876,6 +1326,37 @@ fn toggle_row<'a>(
     .into()
 }
 
+/// A post-install step, indented below its package in the progress list.
+fn step_row(label: &str, status: &StepStatus) -> Element<'static, Message> {
+    let (icon_char, color, status_label) = match status {
+        StepStatus::Pending => (char::from(Icon::Circle), MUTED, String::new()),
+        StepStatus::Running => (char::from(Icon::Loader), STATUS_BLUE, "Running...".into()),
+        StepStatus::Done => (char::from(Icon::CircleCheck), STATUS_GREEN, "Done".into()),
+        StepStatus::Warning(e) => (
+            char::from(Icon::TriangleAlert),
+            STATUS_AMBER,
+            format!("Warning: {e}"),
+        ),
+        StepStatus::Failed(e) => (
+            char::from(Icon::CircleX),
+            STATUS_RED,
+            format!("Failed: {e}"),
+        ),
+        StepStatus::Skipped => (char::from(Icon::CircleMinus), MUTED, "Skipped".into()),
+    };
+
+    row![
+        text(icon_char).size(12).font(LUCIDE_FONT).color(color),
+        text(label.to_string()).size(12).color(MUTED),
+        iced::widget::Space::new().width(Length::Fill),
+        text(status_label).size(11).color(color),
+    ]
+    .spacing(8)
+    .padding(padding::left(22).top(1).bottom(1))
+    .align_y(iced::Alignment::Center)
+    .into()
+}
+
 /// Terminal log box: monospace text in a dark container, auto-scrolled to bottom.
 fn terminal_log_box<'a>(log: &[String], live_line: &str) -> iced::widget::Container<'a, Message> {
     let mut terminal_text = log.join("\n");
@@ -1045,6 +1526,10 @@ fn view_progress_screen<'a>(
         .align_y(iced::Alignment::Center);
 
         pkg_list = pkg_list.push(pkg_row);
+
+        for (label, status) in state.steps.get(i).into_iter().flatten() {
+            pkg_list = pkg_list.push(step_row(label, status));
+        }
     }
 
     let scrollable_pkgs = scrollable(pkg_list)
@@ -1121,18 +1606,107 @@ fn view_progress_screen<'a>(
         .into()
 }
 
-/// Single package row for the 2-column grid in package select.
-fn package_row<'a>(pkg: &'a Package, app: &'a App) -> Element<'a, Message> {
-    let is_checked = app.selected.contains(&pkg.id);
-    let id = pkg.id.clone();
-    let installed = app.is_installed(pkg);
+/// Split rows evenly into a left and a right column.
+fn two_columns(rows: Vec<Element<'_, Message>>) -> Element<'_, Message> {
+    let half = rows.len().div_ceil(2);
+    let mut left_col = column![].spacing(2);
+    let mut right_col = column![].spacing(2);
+
+    for (i, row) in rows.into_iter().enumerate() {
+        if i < half {
+            left_col = left_col.push(row);
+        } else {
+            right_col = right_col.push(row);
+        }
+    }
+
+    row![
+        left_col.width(Length::FillPortion(1)),
+        right_col.width(Length::FillPortion(1)),
+    ]
+    .spacing(32)
+    .into()
+}
+
+/// Text with the given byte ranges highlighted, for search results.
+fn highlighted<'a>(
+    content: &'a str,
+    ranges: &[Range<usize>],
+    size: u32,
+    color: iced::Color,
+) -> Element<'a, Message> {
+    let mut spans: Vec<Span<'a>> = Vec::new();
+    let mut pos = 0;
+    for r in ranges {
+        if r.start > pos {
+            spans.push(span(&content[pos..r.start]).color(color));
+        }
+        spans.push(span(&content[r.clone()]).color(STATUS_BLUE));
+        pos = r.end;
+    }
+    if pos < content.len() {
+        spans.push(span(&content[pos..]).color(color));
+    }
+    rich_text(spans).size(size).into()
+}
 
+/// Checkbox with a separate label, so the label can carry search highlights.
+/// Clicking the label toggles the checkbox, like a regular checkbox label.
+fn highlighted_checkbox<'a>(
+    is_checked: bool,
+    label: &'a str,
+    ranges: &[Range<usize>],
+    on_toggle: Option<Message>,
+) -> Element<'a, Message> {
+    let Some(on_toggle) = on_toggle else {
+        let cb = checkbox(is_checked).size(16).style(package_checkbox_style);
+        return row![cb, highlighted(label, ranges, 14, MUTED)]
+            .spacing(8)
+            .align_y(iced::Alignment::Center)
+            .into();
+    };
+
+    let toggle = on_toggle.clone();
     let cb = checkbox(is_checked)
-        .label(&pkg.name)
-        .on_toggle(move |_| Message::TogglePackage(id.clone()))
+        .on_toggle(move |_| toggle.clone())
         .size(16)
-        .text_size(14)
         .style(package_checkbox_style);
+    let label = mouse_area(highlighted(label, ranges, 14, TEXT))
+        .on_press(on_toggle)
+        .interaction(iced::mouse::Interaction::Pointer);
+
+    row![cb, label]
+        .spacing(8)
+        .align_y(iced::Alignment::Center)
+        .into()
+}
+
+/// Single package row for the 2-column grid in package select.
+fn package_row<'a>(pkg: &'a Package, app: &'a App, found: &Match) -> Element<'a, Message> {
+    let installed = app.is_installed(pkg);
+    // Anything installed can be removed, whether or not it could be installed
+    let (on_toggle, disabled_reason) = if app.uninstall_mode {
+        match pkg.uninstaller(&[]) {
+            Some(_) => (
+                pkg.installed_key
+                    .clone()
+                    .map(Message::ToggleUninstallPackage),
+                None,
+            ),
+            None => (
+                None,
+                Some("No uninstall command in the catalog".to_string()),
+            ),
+        }
+    } else {
+        let incompatibility = pkg.incompatibility(&app.system);
+        let on_toggle = incompatibility
+            .is_none()
+            .then(|| Message::TogglePackage(pkg.id.clone()));
+        (on_toggle, incompatibility)
+    };
+
+    let cb = highlighted_checkbox(app.is_checked(pkg), &pkg.name, &found.name, on_toggle);
 
     let is_browser = pkg.is_browser_download();
 
@@ -1159,8 +1733,244 @@ fn package_row<'a>(pkg: &'a Package, app: &'a App) -> Element<'a, Message> {
             .padding([1, 6]);
         pkg_row = pkg_row.push(badge);
     }
+    pkg_row = pkg_row.push(icon_button(
+        Icon::Info,
+        Some(Message::ShowDetails(pkg.id.clone())),
+    ));
+
+    let mut col = column![pkg_row].spacing(2);
+    if let Some(reason) = disabled_reason {
+        let note = text(reason).size(12).color(STATUS_AMBER);
+        col = col.push(container(note).padding(padding::left(24)));
+    }
+    // Show the description when the search matched it, so the hit is visible
+    if !found.description.is_empty() {
+        let desc = highlighted(&pkg.description, &found.description, 12, MUTED);
+        col = col.push(container(desc).padding(padding::left(24)));
+    }
+    container(col).padding([4, 0]).into()
+}
+
+/// Below the picker's search results: a button that runs `winget search` for
+/// the query, then its results, which can be ticked like catalog packages.
+fn winget_search_section(app: &App) -> Element<'_, Message> {
+    let state = &app.winget_search;
+    let query = app.search.trim();
+
+    if state.query != query || state.running {
+        let label = if state.running {
+            "Searching winget...".to_string()
+        } else {
+            format!("Search winget for \u{201c}{query}\u{201d}")
+        };
+        let search_btn = button(
+            row![
+                text(char::from(Icon::Search)).size(14).font(LUCIDE_FONT),
+                text(label).size(13),
+            ]
+            .spacing(6)
+            .align_y(iced::Alignment::Center),
+        )
+        .on_press_maybe((!state.running).then_some(Message::SearchWinget))
+        .style(ghost_button_style)
+        .padding([6, 12]);
+        return search_btn.into();
+    }
+
+    let label = text(format!("WINGET \u{2014} {} RESULTS", state.results.len()))
+        .size(11)
+        .color(MUTED_FG);
+    let body: Element<'_, Message> = if let Some(ref error) = state.error {
+        text(format!("winget search failed: {error}"))
+            .size(12)
+            .color(STATUS_RED)
+            .into()
+    } else if state.results.is_empty() {
+        text("No winget packages match")
+            .size(12)
+            .color(MUTED)
+            .into()
+    } else {
+        let rows = state
+            .results
+            .iter()
+            .enumerate()
+            .map(|(i, result)| search_result_row(i, result, app))
+            .collect();
+        two_columns(rows)
+    };
+    column![container(label).padding([2, 6]), body]
+        .spacing(6)
+        .into()
+}
+
+/// A `winget search` result in the picker; ticking it selects the matching
+/// catalog package, or adds the result as an ad-hoc one.
+fn search_result_row<'a>(
+    index: usize,
+    result: &'a SearchResult,
+    app: &'a App,
+) -> Element<'a, Message> {
+    let pkg = app.search_result_package(result);
+    let cb = highlighted_checkbox(
+        pkg.is_some_and(|p| app.selected.contains(&p.id)),
+        &result.name,
+        &[],
+        Some(Message::ToggleWingetResult(index)),
+    );
+
+    let mut name_row = row![cb].spacing(8).align_y(iced::Alignment::Center);
+    if app.installed.contains_key(&result.id.to_lowercase()) {
+        let badge_label = text("Installed").size(10).color(STATUS_GREEN);
+        let badge = container(badge_label)
+            .style(installed_badge_style)
+            .padding([1, 6]);
+        name_row = name_row.push(badge);
+    }
+    if pkg.is_some_and(|p| !p.is_ad_hoc()) {
+        name_row = name_row.push(text("In catalog").size(10).color(MUTED));
+    }
 
-    container(pkg_row).padding([4, 0]).into()
+    let mut id_label = result.id.clone();
+    for extra in [&result.version, &result.source] {
+        if !extra.is_empty() {
+            id_label.push_str(&format!(" \u{00b7} {extra}"));
+        }
+    }
+    let id = text(id_label)
+        .size(11)
+        .font(iced::Font::MONOSPACE)
+        .color(MUTED);
+    let col = column![name_row, container(id).padding(padding::left(24))].spacing(2);
+    container(col).padding([4, 0]).into()
+}
+
+/// Side panel in the picker: the catalog's metadata for a package, filled
+/// in with live `winget show` data for winget and Store packages.
+fn details_panel<'a>(pkg: &'a Package, app: &'a App) -> Element<'a, Message> {
+    let live = app.winget_details.get(&pkg.id);
+    let fetched = live.and_then(|l| l.as_ref()).and_then(|r| r.as_ref().ok());
+    // The catalog's own value wins over winget's
+    let pick = |catalog: &'a Option<String>, winget: Option<&'a String>| {
+        catalog
+            .as_deref()
+            .or(winget.map(String::as_str))
+            .filter(|v| !v.is_empty())
+    };
+
+    let mut title = row![].spacing(8).align_y(iced::Alignment::Center);
+    if let Some(icon) = pkg.icon() {
+        title = title.push(text(icon).size(16).font(LUCIDE_FONT).color(MUTED_FG));
+    }
+    let header = title
+        .push(text(&pkg.name).size(16))
+        .push(iced::widget::Space::new().width(Length::Fill))
+        .push(icon_button(Icon::X, Some(Message::CloseDetails)));
+
+    let mut col = column![header, text(&pkg.description).size(13).color(MUTED_FG)].spacing(14);
+
+    if let Some(publisher) = pick(&pkg.publisher, fetched.map(|d| &d.publisher)) {
+        col = col.push(detail_field("Publisher", text(publisher).size(13).into()));
+    }
+    if let Some(license) = pick(&pkg.license, fetched.map(|d| &d.license)) {
+        col = col.push(detail_field("License", text(license).size(13).into()));
+    }
+    if let Some(homepage) = pick(&pkg.homepage, fetched.map(|d| &d.homepage)) {
+        col = col.push(detail_field("Homepage", link(homepage)));
+    }
+    let installed = pkg
+        .installed_key
+        .as_ref()
+        .and_then(|key| app.installed.get(key))
+        .filter(|p| !p.version.is_empty());
+    if let Some(installed) = installed {
+        let version = text(&installed.version).size(13);
+        col = col.push(detail_field("Installed version", version.into()));
+    }
+    if let Some(details) = fetched {
+        col = col.push(detail_field(
+            "Latest version",
+            text(&details.version).size(13).into(),
+        ));
+        if !details.installer_type.is_empty() {
+            let installer_type = text(&details.installer_type).size(13);
+            col = col.push(detail_field("Installer type", installer_type.into()));
+        }
+        if !details.release_notes_url.is_empty() {
+            col = col.push(detail_field(
+                "Release notes",
+                link(&details.release_notes_url),
+            ));
+        }
+    }
+    if let Some(ref notes) = pkg.notes {
+        col = col.push(detail_field(
+            "Notes",
+            text(notes).size(13).color(STATUS_AMBER).into(),
+        ));
+    }
+
+    match live {
+        Some(None) => {
+            let loading = text("Loading details from winget...").size(12).color(MUTED);
+            col = col.push(loading);
+        }
+        Some(Some(Err(e))) => {
+            let error = text(format!("winget show failed: {e}"))
+                .size(12)
+                .color(STATUS_RED);
+            col = col.push(error);
+        }
+        _ => {}
+    }
+
+    container(scrollable(col).height(Length::Fill))
+        .style(icon_box_style)
+        .padding(16)
+        .width(300)
+        .height(Length::Fill)
+        .into()
+}
+
+/// A small uppercase label over a value, in the details panel.
+fn detail_field<'a>(label: &str, value: Element<'a, Message>) -> Element<'a, Message> {
+    column![text(label.to_uppercase()).size(10).color(MUTED), value]
+        .spacing(2)
+        .into()
+}
+
+/// A URL that opens in the browser when clicked.
+fn link(url: &str) -> Element<'_, Message> {
+    button(text(url).size(13).color(STATUS_BLUE))
+        .on_press(Message::OpenUrl(url.to_string()))
+        .style(ghost_button_style)
+        .padding(0)
+        .into()
+}
+
+/// Installed software outside the catalog, in the picker's uninstall mode.
+fn installed_row<'a>(
+    pkg: &'a InstalledPackage,
+    app: &'a App,
+    found: &Match,
+) -> Element<'a, Message> {
+    let cb = highlighted_checkbox(
+        app.uninstall_selected.contains(&pkg.key),
+        &pkg.name,
+        &found.name,
+        Some(Message::ToggleUninstallPackage(pkg.key.clone())),
+    );
+    let id_label = if pkg.version.is_empty() {
+        pkg.id.clone()
+    } else {
+        format!("{} \u{00b7} {}", pkg.id, pkg.version)
+    };
+    let id = text(id_label)
+        .size(11)
+        .font(iced::Font::MONOSPACE)
+        .color(MUTED);
+    let col = column![cb, container(id).padding(padding::left(24))].spacing(2);
+    container(col).padding([4, 0]).into()
 }
 
 /// Small icon + label row used for status indicators (e.g. catalog source, scan progress).
@@ -1177,6 +1987,15 @@ fn status_indicator(icon: Icon, label: String, color: iced::Color) -> Element<'s
     .into()
 }
 
+/// A toggleable filter chip for a package tag.
+fn tag_chip(tag: &str, active: bool) -> Element<'_, Message> {
+    button(text(tag).size(12))
+        .on_press(Message::ToggleTag(tag.to_string()))
+        .style(move |theme: &Theme, status| tab_style(theme, status, active))
+        .padding([4, 10])
+        .into()
+}
+
 /// A tab button for the settings screen.
 fn tab_button<'a>(
     label: &'a str,
@@ -1246,8 +2065,8 @@ fn view_settings_changelog<'a>() -> Element<'a, Message> {
         .into()
 }
 
-fn profile_card(profile: Profile, selected: Option<Profile>) -> Element<'static, Message> {
-    let is_selected = selected == Some(profile);
+fn profile_card<'a>(profile: &'a Profile, selected: Option<&str>) -> Element<'a, Message> {
+    let is_selected = selected == Some(profile.slug.as_str());
 
     // Icon in a small bordered box
     let icon = text(profile.icon())
@@ -1261,8 +2080,8 @@ fn profile_card(profile: Profile, selected: Option<Profile>) -> Element<'static,
         .center_y(36);
 
     // Text column
-    let title = text(profile.title()).size(14);
-    let desc = text(profile.description()).size(12).color(MUTED_FG);
+    let title = text(&profile.title).size(14);
+    let desc = text(&profile.description).size(12).color(MUTED_FG);
     let text_col = column![title, desc].spacing(2);
 
     // Horizontal layout: icon box + text
@@ -1273,7 +2092,7 @@ fn profile_card(profile: Profile, selected: Option<Profile>) -> Element<'static,
         .width(Length::Fill);
 
     button(card_content)
-        .on_press(Message::ProfileSelected(profile))
+        .on_press(Message::ProfileSelected(profile.slug.clone()))
         .width(Length::Fill)
         .style(move |theme: &Theme, status| card_style(theme, status, is_selected))
         .into()
@@ -0,0 +1,96 @@
+//! Runs `lint_packages` on the catalogs in `tests/fixtures/lint`, each
+//! broken in one way.
+
+use std::process::Command;
+
+/// Path of a lint fixture, as passed to the linter and echoed in its output.
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/lint/{name}", env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Lint a fixture: whether it passed, and what was printed to stderr.
+fn lint(path: &str) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_lint_packages"))
+        .arg(path)
+        .output()
+        .expect("lint_packages should run");
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    (output.status.success(), stderr)
+}
+
+/// Lint a fixture that should fail with exactly one `problem` on `line`.
+fn assert_problem(name: &str, line: usize, problem: &str) {
+    let path = fixture(name);
+    let (passed, stderr) = lint(&path);
+    assert!(!passed, "{name} should fail the lint");
+    assert!(
+        stderr.contains(&format!("{path}:{line}: {problem}\n")),
+        "{name}: expected line {line} to report `{problem}`, got:\n{stderr}"
+    );
+    assert!(stderr.contains("1 problem(s) found"), "{stderr}");
+}
+
+#[test]
+fn valid_catalog_passes() {
+    let (passed, stderr) = lint(&fixture("valid.toml"));
+    assert!(passed, "{stderr}");
+}
+
+#[test]
+fn duplicate_id_is_reported_on_the_second_package() {
+    assert_problem(
+        "duplicate_id.toml",
+        20,
+        "[git] duplicate id (first defined on line 13)",
+    );
+}
+
+#[test]
+fn unknown_category_is_reported() {
+    assert_problem(
+        "unknown_category.toml",
+        13,
+        "[git] unknown category 'games'",
+    );
+}
+
+#[test]
+fn unknown_profile_is_reported() {
+    assert_problem("unknown_profile.toml", 13, "[git] unknown profile 'gamer'");
+}
+
+#[test]
+fn package_needs_an_install_method() {
+    assert_problem(
+        "no_install_method.toml",
+        13,
+        "[git] none of winget_id, install_command, or installer is set",
+    );
+}
+
+#[test]
+fn package_has_only_one_install_method() {
+    assert_problem(
+        "both_install_methods.toml",
+        13,
+        "[git] set only one of winget_id, install_command, and installer",
+    );
+}
+
+#[test]
+fn malformed_winget_id_is_reported() {
+    assert_problem(
+        "malformed_winget_id.toml",
+        13,
+        "[git] malformed winget_id 'Git'",
+    );
+}
+
+#[test]
+fn post_install_on_a_browser_download_is_reported() {
+    assert_problem(
+        "browser_download_post_install.toml",
+        13,
+        "[topping] post_install has no effect on a browser download",
+    );
+}